@@ -0,0 +1,62 @@
+//! 对比旧的 `format!` + `hex::encode` 循环与当前无分配搜索循环的单线程吞吐量
+//!
+//! 运行: `cargo bench --bench hot_loop`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use pow_rs::hash::Sha256 as Sha256Hash;
+use pow_rs::simd::Backend;
+use pow_rs::{Difficulty, Searcher};
+use sha2::{Digest, Sha256};
+
+const HASHES: u64 = 2_000_000;
+const PREFIX: &str = "weimeityy";
+
+/// 原实现：每次迭代分配输入字符串和十六进制摘要
+fn legacy_loop(prefix: &str, difficulty: usize, start: u64, end: u64) -> Option<u64> {
+    let target_prefix = "0".repeat(difficulty);
+    let mut hasher = Sha256::new();
+
+    for nonce in start..=end {
+        hasher.reset();
+        let data = format!("{}{}", prefix, nonce);
+        hasher.update(data.as_bytes());
+        let hash_hex = hex::encode(hasher.finalize_reset());
+        if hash_hex.starts_with(&target_prefix) {
+            return Some(nonce);
+        }
+    }
+    None
+}
+
+fn report(name: &str, elapsed: Duration) -> f64 {
+    let rate = HASHES as f64 / elapsed.as_secs_f64();
+    println!("{:<8} {:>10} 哈希，耗时 {:>10.2?}，{:>14.2} 哈希/秒", name, HASHES, elapsed, rate);
+    rate
+}
+
+fn main() {
+    println!("SHA-256 实现: {}", Backend::detect());
+
+    // 64 个十六进制零不可能达到，保证两种实现都跑满全部nonce
+    let start = Instant::now();
+    black_box(legacy_loop(black_box(PREFIX), 64, 0, HASHES - 1));
+    let legacy = report("旧实现", start.elapsed());
+
+    let mut searcher = Searcher::<Sha256Hash>::new(PREFIX.as_bytes(), Difficulty::from_hex_zeros(64));
+    let start = Instant::now();
+    black_box(searcher.search(0, HASHES - 1, |_| true));
+    let current = report("当前实现", start.elapsed());
+
+    println!("提升: {:.2}x", current / legacy);
+
+    // 前缀的完整分组已预先压缩，长前缀不应明显降低速率
+    for len in [64, 256, 1024] {
+        let prefix = "x".repeat(len);
+        let mut searcher = Searcher::<Sha256Hash>::new(prefix.as_bytes(), Difficulty::from_hex_zeros(64));
+        let start = Instant::now();
+        black_box(searcher.search(0, HASHES - 1, |_| true));
+        report(&format!("前缀{}", len), start.elapsed());
+    }
+}
@@ -0,0 +1,357 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::difficulty::{ByteOrder, Difficulty, Target};
+use crate::encoding::NonceEncoding;
+use crate::hash::Algorithm;
+use crate::schedule::RangeSet;
+
+/// 检查点文件第一行的格式标识
+const HEADER: &str = "# pow_rs checkpoint v1";
+
+/// 挖矿任务的参数和已搜索完的nonce区间，用于中断后继续搜索
+///
+/// 文件为逐行的 `key=value` 文本，前缀以十六进制保存以避免换行等特殊字符。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub prefix: String,
+    pub difficulty: Difficulty,
+    pub algorithm: Algorithm,
+    pub nonce_encoding: NonceEncoding,
+    pub start: u64,
+    pub end: u64,
+    pub lowest_nonce: bool,
+    /// 需要的结果数量，`None` 表示找出范围内的全部结果
+    pub count: Option<u64>,
+    /// 是否为只求最佳哈希的 anytime 模式
+    pub anytime: bool,
+    /// 是否为难度阶梯模式
+    pub ladder: bool,
+    /// 已搜索完的nonce
+    pub covered: RangeSet,
+    /// 已搜索区间中找到的结果，按升序排列
+    pub solutions: Vec<u64>,
+    /// 难度阶梯上目前达到的比特数和对应的最小nonce，按比特数升序排列
+    pub milestones: Vec<(u32, u64)>,
+}
+
+impl Checkpoint {
+    /// 从文件读取检查点
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// 写入检查点，先写临时文件再重命名，避免中途崩溃留下损坏的文件
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        fs::write(&tmp, self.to_text())?;
+        fs::rename(&tmp, path)
+    }
+
+    /// 序列化为文本
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "{}", HEADER);
+        let _ = writeln!(text, "prefix={}", hex::encode(&self.prefix));
+        let _ = writeln!(text, "difficulty={}", format_difficulty(&self.difficulty));
+        let _ = writeln!(text, "algorithm={}", self.algorithm);
+        let _ = writeln!(text, "nonce_encoding={}", self.nonce_encoding);
+        let _ = writeln!(text, "start={}", self.start);
+        let _ = writeln!(text, "end={}", self.end);
+        let _ = writeln!(text, "lowest_nonce={}", self.lowest_nonce);
+        let count = self.count.map_or_else(|| "all".to_string(), |count| count.to_string());
+        let _ = writeln!(text, "count={}", count);
+        let _ = writeln!(text, "anytime={}", self.anytime);
+        let _ = writeln!(text, "ladder={}", self.ladder);
+
+        let covered: Vec<String> = self
+            .covered
+            .ranges()
+            .iter()
+            .map(|(first, last)| format!("{}-{}", first, last))
+            .collect();
+        let _ = writeln!(text, "covered={}", covered.join(","));
+        let solutions: Vec<String> = self.solutions.iter().map(u64::to_string).collect();
+        let _ = writeln!(text, "solutions={}", solutions.join(","));
+        let milestones: Vec<String> = self
+            .milestones
+            .iter()
+            .map(|(bits, nonce)| format!("{}:{}", bits, nonce))
+            .collect();
+        let _ = writeln!(text, "milestones={}", milestones.join(","));
+        text
+    }
+
+    /// 从文本解析
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut lines = text.lines();
+        if lines.next().map(str::trim) != Some(HEADER) {
+            return Err(invalid("不是 pow_rs 检查点文件"));
+        }
+
+        let mut prefix = None;
+        let mut difficulty = None;
+        // 早期的检查点没有这两个字段，只可能是 SHA-256 和十进制nonce
+        let mut algorithm = Algorithm::Sha256;
+        let mut nonce_encoding = NonceEncoding::Decimal;
+        let mut start = None;
+        let mut end = None;
+        let mut lowest_nonce = false;
+        let mut count = Some(1);
+        let mut anytime = false;
+        let mut ladder = false;
+        let mut covered = RangeSet::new();
+        // 早期的检查点没有保存结果
+        let mut solutions = Vec::new();
+        let mut milestones = Vec::new();
+
+        // 行尾的空格可能属于字符集nonce，只去掉行首空白和 Windows 换行符
+        for line in lines.map(|line| line.trim_start().trim_end_matches('\r')).filter(|line| !line.is_empty()) {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("无法解析的行: {}", line)))?;
+
+            match key {
+                "prefix" => {
+                    let bytes = hex::decode(value).map_err(|_| invalid("前缀不是合法的十六进制"))?;
+                    prefix = Some(String::from_utf8(bytes).map_err(|_| invalid("前缀不是合法的 UTF-8"))?);
+                }
+                "difficulty" => difficulty = Some(parse_difficulty(value)?),
+                "algorithm" => algorithm = value.parse().map_err(invalid)?,
+                "nonce_encoding" => nonce_encoding = value.parse().map_err(invalid)?,
+                "start" => start = Some(parse_u64(value)?),
+                "end" => end = Some(parse_u64(value)?),
+                "lowest_nonce" => lowest_nonce = parse_bool(value)?,
+                "anytime" => anytime = parse_bool(value)?,
+                "ladder" => ladder = parse_bool(value)?,
+                "count" => {
+                    count = match value {
+                        "all" => None,
+                        _ => match parse_u64(value)? {
+                            0 => return Err(invalid("结果数量至少为 1")),
+                            count => Some(count),
+                        },
+                    };
+                }
+                "covered" => {
+                    for range in value.split(',').filter(|range| !range.is_empty()) {
+                        let (first, last) = range
+                            .split_once('-')
+                            .ok_or_else(|| invalid(format!("无效的区间: {}", range)))?;
+                        let (first, last) = (parse_u64(first)?, parse_u64(last)?);
+                        if first > last {
+                            return Err(invalid(format!("无效的区间: {}", range)));
+                        }
+                        covered.insert(first, last);
+                    }
+                }
+                "solutions" => {
+                    solutions = value
+                        .split(',')
+                        .filter(|nonce| !nonce.is_empty())
+                        .map(parse_u64)
+                        .collect::<io::Result<_>>()?;
+                }
+                "milestones" => {
+                    for milestone in value.split(',').filter(|milestone| !milestone.is_empty()) {
+                        let (bits, nonce) = milestone
+                            .split_once(':')
+                            .ok_or_else(|| invalid(format!("无效的阶梯记录: {}", milestone)))?;
+                        let bits = bits
+                            .parse()
+                            .map_err(|_| invalid(format!("无效的阶梯记录: {}", milestone)))?;
+                        milestones.push((bits, parse_u64(nonce)?));
+                    }
+                }
+                _ => return Err(invalid(format!("未知的字段: {}", key))),
+            }
+        }
+
+        let missing = |field: &str| invalid(format!("缺少字段: {}", field));
+        let checkpoint = Checkpoint {
+            prefix: prefix.ok_or_else(|| missing("prefix"))?,
+            difficulty: difficulty.ok_or_else(|| missing("difficulty"))?,
+            algorithm,
+            nonce_encoding,
+            start: start.ok_or_else(|| missing("start"))?,
+            end: end.ok_or_else(|| missing("end"))?,
+            lowest_nonce,
+            count,
+            anytime,
+            ladder,
+            covered,
+            solutions,
+            milestones,
+        };
+        if checkpoint.start > checkpoint.end {
+            return Err(invalid("nonce范围起点不能大于终点"));
+        }
+        if checkpoint.start > checkpoint.nonce_encoding.max_nonce() {
+            return Err(invalid("nonce范围起点超出nonce编码可表示的范围"));
+        }
+        if checkpoint.ladder && checkpoint.difficulty.bits().is_none() {
+            return Err(invalid("难度阶梯模式的难度必须是前导零比特数"));
+        }
+        Ok(checkpoint)
+    }
+
+    /// `[start, end]` 中尚未搜索的部分
+    pub fn pending(&self) -> RangeSet {
+        self.covered.complement_within(self.start, self.end)
+    }
+}
+
+fn format_difficulty(difficulty: &Difficulty) -> String {
+    match difficulty {
+        Difficulty::LeadingZeroBits(bits) => format!("bits:{}", bits),
+        Difficulty::Target(target) => {
+            let order = match target.byte_order() {
+                ByteOrder::BigEndian => "big",
+                ByteOrder::LittleEndian => "little",
+            };
+            format!("target:{}:{}", hex::encode(target.to_be_bytes()), order)
+        }
+    }
+}
+
+fn parse_difficulty(value: &str) -> io::Result<Difficulty> {
+    let unknown = || invalid(format!("无效的难度: {}", value));
+
+    match value.split(':').collect::<Vec<_>>().as_slice() {
+        ["bits", bits] => {
+            let bits: u32 = bits.parse().map_err(|_| unknown())?;
+            if bits > crate::difficulty::MAX_BITS {
+                return Err(unknown());
+            }
+            Ok(Difficulty::from_bits(bits))
+        }
+        ["target", value, order] => {
+            let order = match *order {
+                "big" => ByteOrder::BigEndian,
+                "little" => ByteOrder::LittleEndian,
+                _ => return Err(unknown()),
+            };
+            let target = Target::from_hex(value).map_err(|e| invalid(e.to_string()))?;
+            Ok(Difficulty::from_target(target.with_byte_order(order)))
+        }
+        _ => Err(unknown()),
+    }
+}
+
+fn parse_bool(value: &str) -> io::Result<bool> {
+    value
+        .parse()
+        .map_err(|_| invalid(format!("无效的布尔值: {}", value)))
+}
+
+fn parse_u64(value: &str) -> io::Result<u64> {
+    value
+        .parse()
+        .map_err(|_| invalid(format!("无效的整数: {}", value)))
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::Alphabet;
+
+    fn checkpoint() -> Checkpoint {
+        let target = Target::from_compact(0x1d00ffff).unwrap().with_byte_order(ByteOrder::LittleEndian);
+        let mut covered = RangeSet::new();
+        covered.insert(0, 99);
+        covered.insert(200, 299);
+        Checkpoint {
+            prefix: "前缀\nwith newline".to_string(),
+            difficulty: Difficulty::from_target(target),
+            algorithm: Algorithm::Blake3,
+            // 字符集的最后一个字符是空格
+            nonce_encoding: NonceEncoding::Alphabet(Alphabet::new("ab ", 1, 4).unwrap()),
+            start: 0,
+            end: 100,
+            lowest_nonce: true,
+            count: None,
+            anytime: false,
+            ladder: false,
+            covered,
+            solutions: vec![3, 57],
+            milestones: Vec::new(),
+        }
+    }
+
+    /// 最少字段的检查点文本，`extra` 中的行追加在后面
+    fn text(extra: &str) -> String {
+        format!("{}\nprefix=616263\ndifficulty=bits:20\nstart=0\nend=1000\n{}", HEADER, extra)
+    }
+
+    fn error(text: &str) -> String {
+        Checkpoint::parse(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn round_trip() {
+        let checkpoint = checkpoint();
+        let text = checkpoint.to_text();
+        assert!(text.contains("count=all\n"));
+        assert!(text.contains("nonce_encoding=alphabet:1-4:ab \n"));
+        assert_eq!(Checkpoint::parse(&text).unwrap(), checkpoint);
+    }
+
+    #[test]
+    fn round_trip_ladder() {
+        let checkpoint = Checkpoint {
+            difficulty: Difficulty::from_bits(24),
+            nonce_encoding: NonceEncoding::DecimalPadded(8),
+            count: Some(1),
+            ladder: true,
+            solutions: Vec::new(),
+            milestones: vec![(1, 0), (2, 7), (3, 7)],
+            ..checkpoint()
+        };
+        assert_eq!(Checkpoint::parse(&checkpoint.to_text()).unwrap(), checkpoint);
+    }
+
+    #[test]
+    fn parse_defaults_for_older_checkpoints() {
+        let checkpoint = Checkpoint::parse(&text("covered=0-9\r\n")).unwrap();
+        assert_eq!(checkpoint.prefix, "abc");
+        assert_eq!(checkpoint.algorithm, Algorithm::Sha256);
+        assert_eq!(checkpoint.nonce_encoding, NonceEncoding::Decimal);
+        assert_eq!(checkpoint.count, Some(1));
+        assert!(!checkpoint.anytime && !checkpoint.ladder);
+        assert!(checkpoint.solutions.is_empty());
+        assert_eq!(checkpoint.pending().ranges(), &[(10, 1000)]);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_input() {
+        assert_eq!(error("prefix=616263\n"), "不是 pow_rs 检查点文件");
+        assert_eq!(error(&text("no separator")), "无法解析的行: no separator");
+        assert_eq!(error(&text("unknown=1")), "未知的字段: unknown");
+        assert_eq!(error(&format!("{}\ndifficulty=bits:20\nstart=0\nend=1", HEADER)), "缺少字段: prefix");
+        assert_eq!(error(&text("difficulty=bits:257")), "无效的难度: bits:257");
+        assert_eq!(error(&text("difficulty=target:ff:middle")), "无效的难度: target:ff:middle");
+        assert_eq!(error(&text("count=0")), "结果数量至少为 1");
+        assert_eq!(error(&text("lowest_nonce=yes")), "无效的布尔值: yes");
+        assert_eq!(error(&text("covered=9-3")), "无效的区间: 9-3");
+        assert_eq!(error(&text("solutions=1,x")), "无效的整数: x");
+        assert_eq!(error(&text("milestones=1")), "无效的阶梯记录: 1");
+        assert_eq!(error(&text("start=1001")), "nonce范围起点不能大于终点");
+        assert_eq!(
+            error(&text("nonce_encoding=decimal-padded:2\nstart=100")),
+            "nonce范围起点超出nonce编码可表示的范围"
+        );
+        assert_eq!(
+            error(&text("difficulty=target:00ff:big\nladder=true")),
+            "难度阶梯模式的难度必须是前导零比特数"
+        );
+    }
+}
@@ -0,0 +1,646 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+/// 摘要的最大前导零比特数
+pub const MAX_BITS: u32 = 256;
+
+/// 结果数量不超过该值时逐项累加泊松分布，更大时用正态近似
+const EXACT_POISSON_MAX: u64 = 1000;
+
+/// 难度要求
+///
+/// 十六进制前导零是比特模式的特例：每个十六进制零对应 4 个零比特。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    /// 摘要至少有给定数量的前导零比特
+    LeadingZeroBits(u32),
+    /// 摘要作为 256 位整数不大于目标值
+    Target(Target),
+}
+
+impl Difficulty {
+    /// 要求 `bits` 个前导零比特
+    pub fn from_bits(bits: u32) -> Self {
+        assert!(bits <= MAX_BITS, "前导零比特数不能超过 {}", MAX_BITS);
+        Difficulty::LeadingZeroBits(bits)
+    }
+
+    /// 要求 `zeros` 个十六进制前导零
+    pub fn from_hex_zeros(zeros: u32) -> Self {
+        Self::from_bits(zeros * 4)
+    }
+
+    /// 要求摘要不大于 `target`
+    pub fn from_target(target: Target) -> Self {
+        Difficulty::Target(target)
+    }
+
+    /// 期望哈希次数为 `hashes` 的难度，恰好为 2 的整数次幂时用前导零比特表示
+    ///
+    /// `hashes` 必须在 1 到 2^256 之间。
+    pub fn from_expected_hashes(hashes: f64) -> Self {
+        assert!((1.0..=2f64.powi(MAX_BITS as i32)).contains(&hashes), "期望哈希次数必须在 1 到 2^256 之间");
+        let bits = hashes.log2();
+        if bits.fract() == 0.0 && 2f64.powi(bits as i32) == hashes {
+            return Self::from_bits(bits as u32);
+        }
+
+        // 目标值为 2^256 / hashes - 1，目标值很大时减一会被 f64 舍去，从最高字节开始逐字节取出
+        let mut rest = 2f64.powi(MAX_BITS as i32) / hashes - 1.0;
+        let mut value = [0u8; 32];
+        for (i, byte) in value.iter_mut().enumerate() {
+            let scale = 2f64.powi(8 * (31 - i as i32));
+            let digit = (rest / scale).floor().min(255.0);
+            *byte = digit as u8;
+            rest -= digit * scale;
+        }
+        Self::from_target(Target::from_be_bytes(value))
+    }
+
+    /// 前导零模式下的比特数
+    pub fn bits(&self) -> Option<u32> {
+        match self {
+            Difficulty::LeadingZeroBits(bits) => Some(*bits),
+            Difficulty::Target(_) => None,
+        }
+    }
+
+    /// 难度恰好为整数个十六进制零时返回其数量
+    pub fn hex_zeros(&self) -> Option<u32> {
+        self.bits()
+            .filter(|bits| bits.is_multiple_of(4))
+            .map(|bits| bits / 4)
+    }
+
+    /// 摘要是否满足难度要求
+    pub fn is_met_by(&self, digest: &[u8; 32]) -> bool {
+        match self {
+            Difficulty::LeadingZeroBits(bits) => leading_zero_bits(digest) >= *bits,
+            Difficulty::Target(target) => target.is_met_by(digest),
+        }
+    }
+
+    /// 随机摘要满足难度要求所需的期望尝试次数
+    pub fn expected_hashes(&self) -> f64 {
+        match self {
+            Difficulty::LeadingZeroBits(bits) => 2f64.powi(*bits as i32),
+            Difficulty::Target(target) => target.expected_hashes(),
+        }
+    }
+
+    /// 以概率 `probability` 找到结果所需的哈希次数，例如 0.5 对应中位数
+    pub fn hashes_for_probability(&self, probability: f64) -> f64 {
+        -(-probability).ln_1p() * self.expected_hashes()
+    }
+
+    /// 等价的目标值：`n` 个前导零比特对应 2^(256-n) - 1
+    pub fn to_target(&self) -> Target {
+        match self {
+            Difficulty::LeadingZeroBits(bits) => {
+                let mut value = [0u8; 32];
+                for (i, byte) in value.iter_mut().enumerate() {
+                    let zeros = bits.saturating_sub(i as u32 * 8).min(8);
+                    *byte = (0xffu16 >> zeros) as u8;
+                }
+                Target::from_be_bytes(value)
+            }
+            Difficulty::Target(target) => *target,
+        }
+    }
+
+    /// 尝试 `hashes` 次后至少找到一个结果的概率
+    pub fn success_probability(&self, hashes: u64) -> f64 {
+        -(-(hashes as f64) / self.expected_hashes()).exp_m1()
+    }
+
+    /// 尝试 `hashes` 次后至少找到 `count` 个结果的概率（泊松近似），计算量与 `count` 无关
+    pub fn count_probability(&self, count: u64, hashes: u64) -> f64 {
+        match count {
+            0 => return 1.0,
+            1 => return self.success_probability(hashes),
+            _ => {}
+        }
+
+        let lambda = hashes as f64 / self.expected_hashes();
+        if count > EXACT_POISSON_MAX {
+            // P(命中次数 >= count) = P(Gamma(count) <= λ)，用 Wilson-Hilferty 变换近似为正态分布
+            let k = count as f64;
+            let z = ((lambda / k).cbrt() - (1.0 - 1.0 / (9.0 * k))) * 3.0 * k.sqrt();
+            return normal_cdf(z);
+        }
+
+        // 1 - P(命中次数 < count)，在对数域中累加以免 e^-λ 下溢
+        let mut ln_term = -lambda;
+        let mut below = 0f64;
+        for k in 0..count {
+            if k > 0 {
+                ln_term += lambda.ln() - (k as f64).ln();
+            }
+            below += ln_term.exp();
+        }
+        (1.0 - below).clamp(0.0, 1.0)
+    }
+
+    /// 摘要作为 256 位整数时使用的字节序，前导零模式下为大端
+    pub fn byte_order(&self) -> ByteOrder {
+        match self {
+            Difficulty::LeadingZeroBits(_) => ByteOrder::BigEndian,
+            Difficulty::Target(target) => target.byte_order(),
+        }
+    }
+
+    /// 按本难度的字节序比较两个摘要，数值越小工作量越大
+    pub fn compare_digests(&self, a: &[u8; 32], b: &[u8; 32]) -> Ordering {
+        match self.byte_order() {
+            ByteOrder::BigEndian => a.cmp(b),
+            ByteOrder::LittleEndian => a.iter().rev().cmp(b.iter().rev()),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difficulty::LeadingZeroBits(bits) => match self.hex_zeros() {
+                Some(zeros) => write!(f, "{} 个十六进制前导零", zeros),
+                None => write!(f, "{} 个前导零比特", bits),
+            },
+            Difficulty::Target(target) => write!(f, "目标值 {}", target),
+        }
+    }
+}
+
+/// 标准正态分布的累积分布函数
+fn normal_cdf(z: f64) -> f64 {
+    0.5 * erfc(-z / std::f64::consts::SQRT_2)
+}
+
+/// 互补误差函数，Chebyshev 拟合，相对误差小于 1.2e-7
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398 + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let value = t * poly.exp();
+    if x >= 0.0 { value } else { 2.0 - value }
+}
+
+/// 统计原始摘要的前导零比特数
+pub fn leading_zero_bits(digest: &[u8]) -> u32 {
+    let mut bits = 0;
+    for &byte in digest {
+        if byte != 0 {
+            return bits + byte.leading_zeros();
+        }
+        bits += 8;
+    }
+    bits
+}
+
+/// 摘要转换为 256 位整数时使用的字节序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ByteOrder {
+    /// 摘要第一个字节为最高位，与十六进制输出的前导零一致
+    #[default]
+    BigEndian,
+    /// 摘要最后一个字节为最高位，与比特币区块哈希的比较方式一致
+    LittleEndian,
+}
+
+/// 256 位目标值，摘要不大于目标值即满足要求
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Target {
+    /// 大端表示的目标值
+    value: [u8; 32],
+    order: ByteOrder,
+}
+
+impl Target {
+    /// 由大端字节表示的目标值创建
+    pub fn from_be_bytes(value: [u8; 32]) -> Self {
+        Target {
+            value,
+            order: ByteOrder::BigEndian,
+        }
+    }
+
+    /// 解析十六进制目标值（大端书写，可带 `0x` 前缀，不足 64 位时左侧补零）
+    pub fn from_hex(s: &str) -> Result<Self, ParseTargetError> {
+        let digits = s.strip_prefix("0x").unwrap_or(s);
+        if digits.is_empty() {
+            return Err(ParseTargetError::InvalidHex);
+        }
+        if digits.len() > 64 {
+            return Err(ParseTargetError::Overflow);
+        }
+
+        let padded = format!("{:0>64}", digits);
+        let mut value = [0u8; 32];
+        hex::decode_to_slice(padded, &mut value).map_err(|_| ParseTargetError::InvalidHex)?;
+        Ok(Self::from_be_bytes(value))
+    }
+
+    /// 解码比特币的紧凑格式 `nBits`：高 8 位为字节长度，低 23 位为尾数
+    pub fn from_compact(nbits: u32) -> Result<Self, ParseTargetError> {
+        let size = (nbits >> 24) as i32;
+        let word = nbits & 0x007f_ffff;
+
+        if word != 0 && nbits & 0x0080_0000 != 0 {
+            return Err(ParseTargetError::Negative);
+        }
+        if word != 0 && (size > 34 || (word > 0xff && size > 33) || (word > 0xffff && size > 32)) {
+            return Err(ParseTargetError::Overflow);
+        }
+
+        // 尾数的三个字节依次对应 256^(size-1)、256^(size-2)、256^(size-3)
+        let mut value = [0u8; 32];
+        for k in 0..3 {
+            let significance = size - 1 - k;
+            let byte = (word >> (8 * (2 - k))) as u8;
+            if (0..32).contains(&significance) {
+                value[31 - significance as usize] = byte;
+            }
+        }
+        Ok(Self::from_be_bytes(value))
+    }
+
+    /// 编码为比特币的紧凑格式 `nBits`，尾数只保留最高的三个字节
+    pub fn to_compact(&self) -> u32 {
+        let mut size = self.value.iter().skip_while(|&&byte| byte == 0).count() as u32;
+        let significant = &self.value[32 - size as usize..];
+        let mut word = significant
+            .iter()
+            .take(3)
+            .fold(0u32, |acc, &byte| (acc << 8) | byte as u32);
+        if size < 3 {
+            word <<= 8 * (3 - size);
+        }
+
+        // 尾数最高位是符号位，置位时多用一个字节
+        if word & 0x0080_0000 != 0 {
+            word >>= 8;
+            size += 1;
+        }
+        (size << 24) | word
+    }
+
+    /// 指定摘要转换为整数时使用的字节序
+    pub fn with_byte_order(mut self, order: ByteOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn byte_order(&self) -> ByteOrder {
+        self.order
+    }
+
+    /// 大端表示的目标值
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        self.value
+    }
+
+    /// 随机摘要不大于目标值所需的期望尝试次数，即 2^256 / (目标值 + 1)
+    pub fn expected_hashes(&self) -> f64 {
+        let value = self.value.iter().fold(0f64, |acc, &byte| acc * 256.0 + byte as f64);
+        2f64.powi(256) / (value + 1.0)
+    }
+
+    /// 按字节序把摘要当作 256 位整数与目标值比较
+    pub fn compare(&self, digest: &[u8; 32]) -> Ordering {
+        match self.order {
+            ByteOrder::BigEndian => digest.iter().cmp(self.value.iter()),
+            ByteOrder::LittleEndian => digest.iter().rev().cmp(self.value.iter()),
+        }
+    }
+
+    /// 摘要是否不大于目标值
+    pub fn is_met_by(&self, digest: &[u8; 32]) -> bool {
+        self.compare(digest) != Ordering::Greater
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let order = match self.order {
+            ByteOrder::BigEndian => "大端",
+            ByteOrder::LittleEndian => "小端",
+        };
+        write!(f, "{}（{}）", hex::encode(self.value), order)
+    }
+}
+
+/// 目标值解析失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseTargetError {
+    /// 不是合法的十六进制
+    InvalidHex,
+    /// 超出 256 位
+    Overflow,
+    /// 紧凑格式的符号位被置位
+    Negative,
+}
+
+impl fmt::Display for ParseTargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseTargetError::InvalidHex => write!(f, "不是合法的十六进制目标值"),
+            ParseTargetError::Overflow => write!(f, "目标值超出 256 位"),
+            ParseTargetError::Negative => write!(f, "紧凑格式目标值为负数"),
+        }
+    }
+}
+
+impl Error for ParseTargetError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 大端字节中只有开头几个字节非零的目标值
+    fn target(leading: &[u8]) -> Target {
+        let mut value = [0u8; 32];
+        value[..leading.len()].copy_from_slice(leading);
+        Target::from_be_bytes(value)
+    }
+
+    #[test]
+    fn compact_genesis_target() {
+        let genesis = Target::from_compact(0x1d00ffff).unwrap();
+        assert_eq!(genesis, target(&[0x00, 0x00, 0x00, 0x00, 0xff, 0xff]));
+        assert_eq!(genesis, Target::from_hex(&format!("00000000ffff{}", "0".repeat(52))).unwrap());
+        assert_eq!(genesis.to_compact(), 0x1d00ffff);
+    }
+
+    #[test]
+    fn compact_sign_bit_is_negative() {
+        assert_eq!(Target::from_compact(0x04923456), Err(ParseTargetError::Negative));
+        assert_eq!(Target::from_compact(0x1d80ffff), Err(ParseTargetError::Negative));
+        // 尾数为零时符号位没有意义
+        assert_eq!(Target::from_compact(0x04800000), Ok(target(&[])));
+    }
+
+    #[test]
+    fn compact_overflow_boundaries() {
+        // 34 字节只能容纳一个字节的尾数，33 字节只能容纳两个
+        assert_eq!(Target::from_compact(0x220000ff), Ok(target(&[0xff])));
+        assert_eq!(Target::from_compact(0x22000100), Err(ParseTargetError::Overflow));
+        assert_eq!(Target::from_compact(0x2100ffff), Ok(target(&[0xff, 0xff])));
+        assert_eq!(Target::from_compact(0x21010000), Err(ParseTargetError::Overflow));
+        assert_eq!(Target::from_compact(0x23000001), Err(ParseTargetError::Overflow));
+        assert_eq!(Target::from_compact(0xff000000), Ok(target(&[])));
+    }
+
+    #[test]
+    fn compact_zero() {
+        assert_eq!(target(&[]).to_compact(), 0);
+        assert_eq!(Target::from_compact(0), Ok(target(&[])));
+    }
+
+    #[test]
+    fn compact_round_trip() {
+        for nbits in [
+            0x1d00ffff, 0x1b0404cb, 0x170331db, 0x2100ffff, 0x05009234, 0x04123456, 0x03123456, 0x02123400,
+            0x01120000,
+        ] {
+            let target = Target::from_compact(nbits).unwrap();
+            assert_eq!(target.to_compact(), nbits, "{:08x}", nbits);
+        }
+        assert_eq!(Target::from_compact(0x05009234), Target::from_hex("92340000"));
+    }
+
+    #[test]
+    fn compare_big_endian() {
+        let target = target(&[0x00, 0xff]);
+        let mut digest = target.to_be_bytes();
+        assert_eq!(target.compare(&digest), Ordering::Equal);
+        assert!(target.is_met_by(&digest));
+
+        digest[1] = 0xfe;
+        digest[31] = 0xff;
+        assert_eq!(target.compare(&digest), Ordering::Less);
+
+        digest[0] = 0x01;
+        assert_eq!(target.compare(&digest), Ordering::Greater);
+        assert!(!target.is_met_by(&digest));
+    }
+
+    #[test]
+    fn compare_little_endian() {
+        let target = target(&[0x00, 0xff]).with_byte_order(ByteOrder::LittleEndian);
+        // 摘要的最后一个字节为最高位
+        let mut digest = target.to_be_bytes();
+        digest.reverse();
+        assert_eq!(target.compare(&digest), Ordering::Equal);
+
+        digest[30] = 0xfe;
+        digest[0] = 0xff;
+        assert_eq!(target.compare(&digest), Ordering::Less);
+
+        let mut digest = [0u8; 32];
+        digest[31] = 0xff;
+        assert_eq!(target.compare(&digest), Ordering::Greater);
+        assert_eq!(target.with_byte_order(ByteOrder::BigEndian).compare(&digest), Ordering::Less);
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!((actual - expected).abs() <= tolerance, "{} 与 {} 相差超过 {}", actual, expected, tolerance);
+    }
+
+    /// 逐项累加的 P(泊松(λ) >= count)，各项的对数减去最大值后再求和以免下溢
+    fn poisson_at_least(count: u64, lambda: f64) -> f64 {
+        let mut ln_factorial = 0.0;
+        let ln_terms: Vec<f64> = (0..count)
+            .map(|k| {
+                if k > 0 {
+                    ln_factorial += (k as f64).ln();
+                }
+                -lambda + k as f64 * lambda.ln() - ln_factorial
+            })
+            .collect();
+        let max = ln_terms.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let below = max.exp() * ln_terms.iter().map(|ln_term| (ln_term - max).exp()).sum::<f64>();
+        1.0 - below
+    }
+
+    #[test]
+    fn erfc_known_values() {
+        for (x, expected) in [
+            (0.0, 1.0),
+            (0.5, 0.479500122),
+            (1.0, 0.157299207),
+            (2.0, 0.004677735),
+            (-1.0, 1.842700793),
+        ] {
+            assert_close(erfc(x), expected, 1e-7);
+        }
+        for (z, expected) in [(0.0, 0.5), (1.959964, 0.975), (-1.0, 0.158655254), (3.0, 0.998650102)] {
+            assert_close(normal_cdf(z), expected, 1e-7);
+        }
+    }
+
+    #[test]
+    fn count_probability_single_result() {
+        let difficulty = Difficulty::from_bits(10);
+        for hashes in [0, 512, 1024, 10_000] {
+            let expected = 1.0 - (-(hashes as f64) / 1024.0).exp();
+            assert_close(difficulty.success_probability(hashes), expected, 1e-12);
+            assert_close(difficulty.count_probability(1, hashes), expected, 1e-12);
+        }
+        assert_eq!(difficulty.count_probability(0, 0), 1.0);
+    }
+
+    #[test]
+    fn count_probability_small_counts() {
+        let difficulty = Difficulty::from_bits(10);
+        // λ = 2：P(N >= 2) = 1 - 3e^-2，P(N >= 3) = 1 - 5e^-2
+        assert_close(difficulty.count_probability(2, 2048), 1.0 - 3.0 * (-2f64).exp(), 1e-12);
+        assert_close(difficulty.count_probability(3, 2048), 1.0 - 5.0 * (-2f64).exp(), 1e-12);
+        assert_eq!(difficulty.count_probability(3, 0), 0.0);
+        for count in [5, 50, 1000] {
+            for lambda in [0.5, 0.9, 1.0, 1.1, 2.0].map(|ratio| ratio * count as f64) {
+                let hashes = (lambda * 1024.0) as u64;
+                assert_close(
+                    difficulty.count_probability(count, hashes),
+                    poisson_at_least(count, hashes as f64 / 1024.0),
+                    1e-9,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn count_probability_normal_approximation() {
+        let difficulty = Difficulty::from_bits(0);
+        // 刚超过逐项累加的范围时与准确值接近
+        for count in [1001, 3000] {
+            for ratio in [0.95, 1.0, 1.05] {
+                let hashes = (ratio * count as f64) as u64;
+                assert_close(
+                    difficulty.count_probability(count, hashes),
+                    poisson_at_least(count, hashes as f64),
+                    2e-3,
+                );
+            }
+        }
+
+        // 数量很大时也能立即算出
+        let count = 1_000_000_000_000;
+        assert_close(difficulty.count_probability(count, count), 0.5, 1e-3);
+        assert_close(difficulty.count_probability(count, count / 100 * 99), 0.0, 1e-9);
+        assert_close(difficulty.count_probability(count, count / 100 * 101), 1.0, 1e-9);
+    }
+
+    /// 恰好有 `zeros` 个前导零比特、其余比特全为 1 的摘要
+    fn digest_with_zero_bits(zeros: u32) -> [u8; 32] {
+        let mut digest = [0u8; 32];
+        for (i, byte) in digest.iter_mut().enumerate() {
+            *byte = (0xffu16 >> zeros.saturating_sub(i as u32 * 8).min(8)) as u8;
+        }
+        digest
+    }
+
+    #[test]
+    fn leading_zero_bit_boundaries() {
+        for zeros in [0, 3, 4, 5, 8, 9, 255, 256] {
+            assert_eq!(leading_zero_bits(&digest_with_zero_bits(zeros)), zeros);
+        }
+        assert_eq!(leading_zero_bits(&[0x00, 0x0f]), 12);
+        assert_eq!(leading_zero_bits(&[]), 0);
+    }
+
+    #[test]
+    fn hex_zeros_and_bits() {
+        assert_eq!(Difficulty::from_hex_zeros(6), Difficulty::from_bits(24));
+        assert_eq!(Difficulty::from_bits(24).hex_zeros(), Some(6));
+        assert_eq!(Difficulty::from_bits(0).hex_zeros(), Some(0));
+        assert_eq!(Difficulty::from_bits(5).hex_zeros(), None);
+        assert_eq!(Difficulty::from_bits(5).bits(), Some(5));
+        assert_eq!(Difficulty::from_target(target(&[0x0f])).bits(), None);
+        assert_eq!(Difficulty::from_bits(256).expected_hashes(), 2f64.powi(256));
+    }
+
+    #[test]
+    fn bits_are_met_at_boundary() {
+        for bits in [0, 3, 4, 5, 256] {
+            let difficulty = Difficulty::from_bits(bits);
+            assert!(difficulty.is_met_by(&digest_with_zero_bits(bits)), "{} 比特", bits);
+            if bits > 0 {
+                assert!(!difficulty.is_met_by(&digest_with_zero_bits(bits - 1)), "{} 比特", bits);
+            }
+        }
+    }
+
+    #[test]
+    fn to_target_is_exact_threshold() {
+        assert_eq!(Difficulty::from_bits(0).to_target(), Target::from_be_bytes([0xff; 32]));
+        assert_eq!(Difficulty::from_bits(4).to_target(), Target::from_hex(&"f".repeat(63)).unwrap());
+        assert_eq!(Difficulty::from_bits(256).to_target(), target(&[]));
+
+        for bits in [0, 3, 4, 5, 17, 255, 256] {
+            let difficulty = Difficulty::from_bits(bits);
+            let threshold = difficulty.to_target().to_be_bytes();
+            // 目标值本身满足，再加一就不满足
+            assert!(difficulty.is_met_by(&threshold));
+            assert!(Difficulty::from_target(difficulty.to_target()).is_met_by(&threshold));
+            if bits > 0 {
+                let above = digest_with_zero_bits(bits - 1);
+                let mut next = threshold;
+                for byte in next.iter_mut().rev() {
+                    let (value, carry) = byte.overflowing_add(1);
+                    *byte = value;
+                    if !carry {
+                        break;
+                    }
+                }
+                assert!(!difficulty.is_met_by(&next), "{} 比特", bits);
+                assert_eq!(leading_zero_bits(&next), bits - 1);
+                assert!(!difficulty.is_met_by(&above));
+            }
+        }
+    }
+
+    #[test]
+    fn display_hex_or_bits() {
+        assert_eq!(Difficulty::from_hex_zeros(6).to_string(), "6 个十六进制前导零");
+        assert_eq!(Difficulty::from_bits(0).to_string(), "0 个十六进制前导零");
+        assert_eq!(Difficulty::from_bits(22).to_string(), "22 个前导零比特");
+        assert!(Difficulty::from_target(target(&[0x0f])).to_string().starts_with("目标值 0f00"));
+    }
+
+    #[test]
+    fn hashes_for_probability_quantiles() {
+        for difficulty in [Difficulty::from_bits(20), Difficulty::from_target(Target::from_compact(0x1d00ffff).unwrap())] {
+            let expected = difficulty.expected_hashes();
+            assert_eq!(difficulty.hashes_for_probability(0.0), 0.0);
+            assert_close(difficulty.hashes_for_probability(0.5), std::f64::consts::LN_2 * expected, 1e-6 * expected);
+            assert_close(difficulty.hashes_for_probability(0.99), 100f64.ln() * expected, 1e-6 * expected);
+            for probability in [0.1, 0.5, 0.9] {
+                let hashes = difficulty.hashes_for_probability(probability).round() as u64;
+                assert_close(difficulty.success_probability(hashes), probability, 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn from_expected_hashes() {
+        assert_eq!(Difficulty::from_expected_hashes(1.0), Difficulty::from_bits(0));
+        assert_eq!(Difficulty::from_expected_hashes(16_777_216.0), Difficulty::from_hex_zeros(6));
+        assert_eq!(Difficulty::from_expected_hashes(2f64.powi(256)), Difficulty::from_bits(256));
+
+        let difficulty = Difficulty::from_expected_hashes(1.5);
+        assert_eq!(difficulty.to_target().to_be_bytes()[..6], [0xaa; 6]);
+        for hashes in [1.5, 3.0, 1e9, 1e12, 4_295_032_833.0] {
+            let difficulty = Difficulty::from_expected_hashes(hashes);
+            assert_close(difficulty.expected_hashes() / hashes, 1.0, 1e-12);
+        }
+        // 目标值只剩约 1.2e7 时，取整误差约为其倒数
+        let difficulty = Difficulty::from_expected_hashes(1e70);
+        assert_close(difficulty.expected_hashes() / 1e70, 1.0, 1e-7);
+    }
+}
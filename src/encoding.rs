@@ -0,0 +1,426 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// u64 十进制表示的最大位数
+pub const MAX_DIGITS: usize = 20;
+
+/// 字符串nonce的最大长度
+pub const MAX_ALPHABET_LEN: u32 = 64;
+
+/// nonce 追加到前缀之后的编码方式
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum NonceEncoding {
+    /// 不补零的十进制文本，例如 `42`
+    #[default]
+    Decimal,
+    /// 左侧补零到固定位数的十进制文本，例如 `decimal-padded:8` 时为 `00000042`
+    DecimalPadded(u32),
+    /// 不补零的小写十六进制文本，例如 `2a`
+    Hex,
+    /// 8 字节小端二进制
+    Le64,
+    /// 8 字节大端二进制
+    Be64,
+    /// 字符集上的字符串，nonce 为字符串在枚举顺序中的序号，见 [`Alphabet`]
+    Alphabet(Alphabet),
+}
+
+impl NonceEncoding {
+    /// 编码后长度不变时可表示的最大nonce，固定位数的十进制为 10^N - 1
+    pub fn max_nonce(&self) -> u64 {
+        match self {
+            NonceEncoding::DecimalPadded(width) => 10u64.checked_pow(*width).map_or(u64::MAX, |n| n - 1),
+            NonceEncoding::Alphabet(alphabet) => alphabet.max_nonce(),
+            _ => u64::MAX,
+        }
+    }
+
+    /// 编码结果是否为二进制，二进制的输入以十六进制显示
+    pub fn is_binary(&self) -> bool {
+        matches!(self, NonceEncoding::Le64 | NonceEncoding::Be64)
+    }
+
+    /// 编码后的最大字节数
+    pub fn max_len(&self) -> usize {
+        match self {
+            NonceEncoding::Decimal | NonceEncoding::DecimalPadded(_) => MAX_DIGITS,
+            NonceEncoding::Hex => 16,
+            NonceEncoding::Le64 | NonceEncoding::Be64 => 8,
+            NonceEncoding::Alphabet(alphabet) => alphabet.max_len() as usize,
+        }
+    }
+
+    /// 把 `nonce` 的编码追加到 `buf`
+    pub fn encode_into(&self, nonce: u64, buf: &mut Vec<u8>) {
+        match self {
+            NonceEncoding::Decimal | NonceEncoding::DecimalPadded(_) => {
+                let mut digits = [0u8; MAX_DIGITS];
+                let mut pos = MAX_DIGITS;
+                let mut rest = nonce;
+                loop {
+                    pos -= 1;
+                    digits[pos] = b'0' + (rest % 10) as u8;
+                    rest /= 10;
+                    if rest == 0 {
+                        break;
+                    }
+                }
+
+                if let NonceEncoding::DecimalPadded(width) = self {
+                    let padding = (*width as usize).saturating_sub(MAX_DIGITS - pos);
+                    buf.resize(buf.len() + padding, b'0');
+                }
+                buf.extend_from_slice(&digits[pos..]);
+            }
+            NonceEncoding::Hex => {
+                let digits = (64 - nonce.leading_zeros()).div_ceil(4).max(1);
+                for i in (0..digits).rev() {
+                    buf.push(b"0123456789abcdef"[(nonce >> (4 * i) & 0xf) as usize]);
+                }
+            }
+            NonceEncoding::Le64 => buf.extend_from_slice(&nonce.to_le_bytes()),
+            NonceEncoding::Be64 => buf.extend_from_slice(&nonce.to_be_bytes()),
+            NonceEncoding::Alphabet(alphabet) => {
+                let mut digits = Vec::with_capacity(alphabet.max_len() as usize);
+                alphabet.digits_into(nonce, &mut digits);
+                buf.extend(digits.iter().map(|&digit| alphabet.chars[digit as usize]));
+            }
+        }
+    }
+
+    /// `nonce` 的编码
+    pub fn encode(&self, nonce: u64) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.max_len());
+        self.encode_into(nonce, &mut buf);
+        buf
+    }
+
+    /// 用于显示的编码结果：文本原样输出，二进制输出十六进制
+    pub fn display(&self, nonce: u64) -> String {
+        let bytes = self.encode(nonce);
+        if self.is_binary() {
+            hex::encode(bytes)
+        } else {
+            String::from_utf8(bytes).expect("文本编码总是 ASCII")
+        }
+    }
+}
+
+impl fmt::Display for NonceEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NonceEncoding::Decimal => f.pad("decimal"),
+            NonceEncoding::DecimalPadded(width) => f.pad(&format!("decimal-padded:{}", width)),
+            NonceEncoding::Hex => f.pad("hex"),
+            NonceEncoding::Le64 => f.pad("le64"),
+            NonceEncoding::Be64 => f.pad("be64"),
+            NonceEncoding::Alphabet(alphabet) => f.pad(&alphabet.to_string()),
+        }
+    }
+}
+
+impl FromStr for NonceEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "decimal" => Ok(NonceEncoding::Decimal),
+            "hex" => Ok(NonceEncoding::Hex),
+            "le64" => Ok(NonceEncoding::Le64),
+            "be64" => Ok(NonceEncoding::Be64),
+            _ if s.starts_with("alphabet:") => s.parse().map(NonceEncoding::Alphabet),
+            _ => {
+                let width = s.strip_prefix("decimal-padded:").ok_or_else(|| {
+                    format!("未知的nonce编码: {}（可用 decimal、decimal-padded:N、hex、le64、be64）", s)
+                })?;
+                match width.parse::<u32>() {
+                    Ok(width @ 1..=20) => Ok(NonceEncoding::DecimalPadded(width)),
+                    _ => Err(format!("补零位数必须是 1 到 {} 之间的整数: {}", MAX_DIGITS, width)),
+                }
+            }
+        }
+    }
+}
+
+/// 由字符集和长度范围构成的nonce空间
+///
+/// 序号从 0 开始，先按长度从短到长，同一长度内按字符在字符集中的顺序逐位枚举，
+/// 例如字符集 `ab`、长度 1-2 时依次为 `a`、`b`、`aa`、`ab`、`ba`、`bb`。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Alphabet {
+    chars: Vec<u8>,
+    min_len: u32,
+    max_len: u32,
+}
+
+impl Alphabet {
+    /// 字符必须是互不相同的可打印 ASCII 字符，且至少有两个
+    pub fn new(chars: impl Into<Vec<u8>>, min_len: u32, max_len: u32) -> Result<Self, String> {
+        let chars = chars.into();
+        if chars.len() < 2 {
+            return Err("字符集至少需要两个字符".to_string());
+        }
+        if let Some(&c) = chars.iter().find(|c| !(b' '..=b'~').contains(*c)) {
+            return Err(format!("字符集只能包含可打印的 ASCII 字符: {:?}", c as char));
+        }
+        if let Some((i, &c)) = chars.iter().enumerate().find(|(i, c)| chars[..*i].contains(c)) {
+            return Err(format!("字符集中的字符重复: {:?}（第 {} 个）", c as char, i + 1));
+        }
+        if min_len == 0 || min_len > max_len || max_len > MAX_ALPHABET_LEN {
+            return Err(format!("长度范围必须满足 1 <= 最小值 <= 最大值 <= {}", MAX_ALPHABET_LEN));
+        }
+        Ok(Alphabet { chars, min_len, max_len })
+    }
+
+    /// 解析字符集，支持 `a-z` 这样的范围；`-` 在开头或结尾时表示它本身
+    pub fn parse_set(s: &str) -> Result<Vec<u8>, String> {
+        let bytes = s.as_bytes();
+        let mut chars = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            if i + 2 < bytes.len() && bytes[i + 1] == b'-' {
+                let (first, last) = (bytes[i], bytes[i + 2]);
+                if first > last {
+                    return Err(format!("无效的字符范围: {}-{}", first as char, last as char));
+                }
+                chars.extend(first..=last);
+                i += 3;
+            } else {
+                chars.push(bytes[i]);
+                i += 1;
+            }
+        }
+        Ok(chars)
+    }
+
+    pub fn chars(&self) -> &[u8] {
+        &self.chars
+    }
+
+    pub fn min_len(&self) -> u32 {
+        self.min_len
+    }
+
+    pub fn max_len(&self) -> u32 {
+        self.max_len
+    }
+
+    /// 字符串总数，超出 u128 时为 `None`
+    pub fn count(&self) -> Option<u128> {
+        let base = self.chars.len() as u128;
+        (self.min_len..=self.max_len).try_fold(0u128, |total, len| total.checked_add(base.checked_pow(len)?))
+    }
+
+    /// 最大的序号，字符串总数超出 u64 时只能搜索前 2^64 个
+    pub fn max_nonce(&self) -> u64 {
+        self.count().map_or(u64::MAX, |count| u64::try_from(count - 1).unwrap_or(u64::MAX))
+    }
+
+    /// 字符串的序号，`digits_into` 的逆运算；长度不在范围内、含有字符集以外的字符或序号超出 u64 时报错
+    pub fn index_of(&self, s: &str) -> Result<u64, String> {
+        let len = s.chars().count() as u32;
+        if !(self.min_len..=self.max_len).contains(&len) {
+            return Err(format!("字符串长度 {} 不在 {}-{} 之间: {}", len, self.min_len, self.max_len, s));
+        }
+        let base = self.chars.len() as u128;
+        let too_large = || format!("字符串的序号超出 u64: {}", s);
+        // 更短的字符串都排在前面
+        let mut index = (self.min_len..len)
+            .try_fold(0u128, |total, len| total.checked_add(base.checked_pow(len)?))
+            .ok_or_else(too_large)?;
+        let mut value = 0u128;
+        for c in s.chars() {
+            let digit = self
+                .chars
+                .iter()
+                .position(|&b| b as char == c)
+                .ok_or_else(|| format!("字符 {:?} 不在字符集中: {}", c, s))?;
+            value = value.checked_mul(base).and_then(|v| v.checked_add(digit as u128)).ok_or_else(too_large)?;
+        }
+        index = index.checked_add(value).ok_or_else(too_large)?;
+        u64::try_from(index).map_err(|_| too_large())
+    }
+
+    /// 第 `index` 个字符串中每个字符在字符集中的下标；超出长度上限的序号继续按更长的长度排列
+    pub(crate) fn digits_into(&self, mut index: u64, digits: &mut Vec<u8>) {
+        let base = self.chars.len() as u64;
+        let mut len = self.min_len;
+        // 字符集至少两个字符，长度最多增加到 64
+        while let Some(count) = base.checked_pow(len).filter(|&count| index >= count) {
+            index -= count;
+            len += 1;
+        }
+
+        digits.clear();
+        digits.resize(len as usize, 0);
+        for digit in digits.iter_mut().rev() {
+            *digit = (index % base) as u8;
+            index /= base;
+        }
+    }
+}
+
+/// 写作 `alphabet:最小长度-最大长度:字符`，字符原样列出
+impl fmt::Display for Alphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chars = String::from_utf8_lossy(&self.chars);
+        write!(f, "alphabet:{}-{}:{}", self.min_len, self.max_len, chars)
+    }
+}
+
+impl FromStr for Alphabet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("无效的字符集nonce: {}（格式为 alphabet:最小长度-最大长度:字符）", s);
+        let (lengths, chars) = s
+            .strip_prefix("alphabet:")
+            .and_then(|rest| rest.split_once(':'))
+            .ok_or_else(invalid)?;
+        let (min_len, max_len) = lengths.split_once('-').ok_or_else(invalid)?;
+        let min_len = min_len.parse().map_err(|_| invalid())?;
+        let max_len = max_len.parse().map_err(|_| invalid())?;
+        Alphabet::new(chars, min_len, max_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(encoding: &str, nonce: u64) -> Vec<u8> {
+        encoding.parse::<NonceEncoding>().unwrap().encode(nonce)
+    }
+
+    #[test]
+    fn encode_text() {
+        assert_eq!(encode("decimal", 0), b"0");
+        assert_eq!(encode("decimal", 88_493), b"88493");
+        assert_eq!(encode("decimal", u64::MAX), b"18446744073709551615");
+        assert_eq!(encode("decimal-padded:8", 42), b"00000042");
+        assert_eq!(encode("decimal-padded:2", 42), b"42");
+        assert_eq!(encode("decimal-padded:2", 123), b"123");
+        assert_eq!(encode("decimal-padded:20", u64::MAX), b"18446744073709551615");
+        assert_eq!(encode("hex", 0), b"0");
+        assert_eq!(encode("hex", 0x2a), b"2a");
+        assert_eq!(encode("hex", 0x1_0000), b"10000");
+        assert_eq!(encode("hex", u64::MAX), b"ffffffffffffffff");
+    }
+
+    #[test]
+    fn encode_binary() {
+        let nonce = 0x0102_0304_0506_0708;
+        assert_eq!(encode("le64", nonce), [8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(encode("be64", nonce), [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(NonceEncoding::Le64.display(nonce), "0807060504030201");
+        assert!(NonceEncoding::Be64.is_binary() && !NonceEncoding::Hex.is_binary());
+    }
+
+    #[test]
+    fn encode_into_appends() {
+        let mut buf = b"prefix".to_vec();
+        NonceEncoding::DecimalPadded(4).encode_into(7, &mut buf);
+        assert_eq!(buf, b"prefix0007");
+    }
+
+    #[test]
+    fn max_nonce() {
+        assert_eq!(NonceEncoding::Decimal.max_nonce(), u64::MAX);
+        assert_eq!(NonceEncoding::Hex.max_nonce(), u64::MAX);
+        assert_eq!(NonceEncoding::Le64.max_nonce(), u64::MAX);
+        assert_eq!(NonceEncoding::DecimalPadded(1).max_nonce(), 9);
+        assert_eq!(NonceEncoding::DecimalPadded(8).max_nonce(), 99_999_999);
+        assert_eq!(NonceEncoding::DecimalPadded(19).max_nonce(), 9_999_999_999_999_999_999);
+        assert_eq!(NonceEncoding::DecimalPadded(20).max_nonce(), u64::MAX);
+    }
+
+    #[test]
+    fn alphabet_order() {
+        let alphabet = Alphabet::new("ab", 1, 2).unwrap();
+        let encoding = NonceEncoding::Alphabet(alphabet.clone());
+        let all: Vec<String> = (0..6).map(|nonce| encoding.display(nonce)).collect();
+        assert_eq!(all, ["a", "b", "aa", "ab", "ba", "bb"]);
+        assert_eq!(alphabet.count(), Some(6));
+        assert_eq!(encoding.max_nonce(), 5);
+
+        // 字符串总数超出 u64 时只能搜索前 2^64 个
+        let huge = Alphabet::new(Alphabet::parse_set("a-z").unwrap(), 1, 64).unwrap();
+        assert_eq!(huge.count(), None);
+        assert_eq!(huge.max_nonce(), u64::MAX);
+    }
+
+    #[test]
+    fn alphabet_index_round_trip() {
+        let alphabet = Alphabet::new("ab", 1, 2).unwrap();
+        let encoding = NonceEncoding::Alphabet(alphabet.clone());
+        for nonce in 0..6 {
+            assert_eq!(alphabet.index_of(&encoding.display(nonce)), Ok(nonce));
+        }
+
+        // README 中的例子
+        let readme = Alphabet::new(Alphabet::parse_set("A-Za-z0-9").unwrap(), 4, 8).unwrap();
+        assert_eq!(readme.index_of("ALAS"), Ok(42_302));
+
+        let huge = Alphabet::new(Alphabet::parse_set("a-z").unwrap(), 1, 64).unwrap();
+        let encoding = NonceEncoding::Alphabet(huge.clone());
+        for nonce in [0, 25, 26, 123_456_789, u64::MAX] {
+            assert_eq!(huge.index_of(&encoding.display(nonce)), Ok(nonce));
+        }
+    }
+
+    #[test]
+    fn alphabet_index_rejects_invalid() {
+        let alphabet = Alphabet::new("ab", 2, 3).unwrap();
+        for s in ["", "a", "abab", "ac", "aé"] {
+            assert!(alphabet.index_of(s).is_err(), "{:?} 应该被拒绝", s);
+        }
+        let huge = Alphabet::new(Alphabet::parse_set("a-z").unwrap(), 1, 64).unwrap();
+        assert!(huge.index_of(&"z".repeat(14)).is_err(), "序号超出 u64 应该被拒绝");
+    }
+
+    #[test]
+    fn parse_set_ranges() {
+        assert_eq!(Alphabet::parse_set("a-d").unwrap(), b"abcd");
+        assert_eq!(Alphabet::parse_set("0-2x-z").unwrap(), b"012xyz");
+        assert_eq!(Alphabet::parse_set("-ab-").unwrap(), b"-ab-");
+        assert!(Alphabet::parse_set("z-a").is_err());
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for encoding in [
+            NonceEncoding::Decimal,
+            NonceEncoding::DecimalPadded(1),
+            NonceEncoding::DecimalPadded(20),
+            NonceEncoding::Hex,
+            NonceEncoding::Le64,
+            NonceEncoding::Be64,
+            NonceEncoding::Alphabet(Alphabet::new("A-Z:", 3, 5).unwrap()),
+            NonceEncoding::Alphabet(Alphabet::new("ab ", 1, 64).unwrap()),
+        ] {
+            assert_eq!(encoding.to_string().parse::<NonceEncoding>(), Ok(encoding.clone()), "{}", encoding);
+        }
+    }
+
+    #[test]
+    fn parse_rejects_invalid() {
+        for invalid in [
+            "decimal-padded:0",
+            "decimal-padded:21",
+            "decimal-padded:",
+            "decimal-padded:x",
+            "octal",
+            "",
+            "alphabet:",
+            "alphabet:1-2",
+            "alphabet:0-2:ab",
+            "alphabet:3-2:ab",
+            "alphabet:1-65:ab",
+            "alphabet:1-2:a",
+            "alphabet:1-2:aba",
+            "alphabet:1-2:é",
+        ] {
+            assert!(invalid.parse::<NonceEncoding>().is_err(), "{:?}", invalid);
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use std::fmt;
+use std::str::FromStr;
+
+use blake2::digest::consts::U32;
+use sha3::Digest;
+
+use crate::sha256::Midstate;
+use crate::simd::{Backend, MAX_LANES};
+
+/// 搜索循环使用的哈希算法
+///
+/// 前缀在创建搜索器时只吸收一次，之后每个nonce从吸收后的状态继续计算。
+pub trait PowHash: Clone + Send + Sync + fmt::Debug {
+    /// 吸收 `prefix` 中可以预先处理的部分，返回状态和尚未吸收的剩余字节
+    fn absorb(prefix: &[u8]) -> (Self, &[u8]);
+
+    /// 从已吸收的状态继续处理 `tail`，得到完整输入的 32 字节摘要
+    fn finalize(&self, tail: &[u8]) -> [u8; 32];
+
+    /// 计算完整输入的摘要
+    fn digest(input: &[u8]) -> [u8; 32] {
+        let (state, tail) = Self::absorb(input);
+        state.finalize(tail)
+    }
+
+    /// 一次可以并行计算的输入数量
+    fn lanes(&self) -> usize {
+        1
+    }
+
+    /// 并行计算 [`lanes`](PowHash::lanes) 个等长输入的摘要，默认逐个计算
+    fn finalize_lanes(&self, tails: &[&[u8]], digests: &mut [[u8; 32]]) {
+        for (tail, digest) in tails.iter().zip(digests) {
+            *digest = self.finalize(tail);
+        }
+    }
+}
+
+/// SHA-256，前缀的完整分组预先压缩为中间状态，CPU支持时多个nonce并行计算
+#[derive(Debug, Clone, Copy)]
+pub struct Sha256 {
+    midstate: Midstate,
+    backend: Backend,
+}
+
+impl PowHash for Sha256 {
+    fn absorb(prefix: &[u8]) -> (Self, &[u8]) {
+        let (midstate, tail) = Midstate::new(prefix);
+        let backend = Backend::detect();
+        (Sha256 { midstate, backend }, tail)
+    }
+
+    fn finalize(&self, tail: &[u8]) -> [u8; 32] {
+        self.midstate.finalize(tail)
+    }
+
+    fn lanes(&self) -> usize {
+        self.backend.lanes()
+    }
+
+    fn finalize_lanes(&self, tails: &[&[u8]], digests: &mut [[u8; 32]]) {
+        finalize_midstate_lanes(&self.midstate, self.backend, tails, digests);
+    }
+}
+
+/// 双重 SHA-256（比特币），SHA-256(SHA-256(输入))
+#[derive(Debug, Clone, Copy)]
+pub struct Sha256d {
+    midstate: Midstate,
+    backend: Backend,
+}
+
+impl PowHash for Sha256d {
+    fn absorb(prefix: &[u8]) -> (Self, &[u8]) {
+        let (midstate, tail) = Midstate::new(prefix);
+        let backend = Backend::detect();
+        (Sha256d { midstate, backend }, tail)
+    }
+
+    fn finalize(&self, tail: &[u8]) -> [u8; 32] {
+        let first = self.midstate.finalize(tail);
+        Midstate::new(&[]).0.finalize(&first)
+    }
+
+    fn lanes(&self) -> usize {
+        self.backend.lanes()
+    }
+
+    fn finalize_lanes(&self, tails: &[&[u8]], digests: &mut [[u8; 32]]) {
+        let mut first = [[0u8; 32]; MAX_LANES];
+        finalize_midstate_lanes(&self.midstate, self.backend, tails, &mut first);
+
+        // 第二轮的输入都是 32 字节，同样可以并行
+        let mut inner = [&[][..]; MAX_LANES];
+        for (input, digest) in inner.iter_mut().zip(&first) {
+            *input = digest;
+        }
+        finalize_midstate_lanes(&Midstate::new(&[]).0, self.backend, &inner[..tails.len()], digests);
+    }
+}
+
+/// 多缓冲区计算失败（输入超过两个分组）时退回逐个计算
+fn finalize_midstate_lanes(midstate: &Midstate, backend: Backend, tails: &[&[u8]], digests: &mut [[u8; 32]]) {
+    if !midstate.finalize_lanes(backend, tails, digests) {
+        for (tail, digest) in tails.iter().zip(digests) {
+            *digest = midstate.finalize(tail);
+        }
+    }
+}
+
+/// 基于 RustCrypto `Digest` 的算法：预先吸收整个前缀，每次克隆状态后追加nonce
+macro_rules! digest_pow_hash {
+    ($(#[$meta:meta])* $name:ident, $inner:ty) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone)]
+        pub struct $name($inner);
+
+        impl PowHash for $name {
+            fn absorb(prefix: &[u8]) -> (Self, &[u8]) {
+                let mut hasher = <$inner>::new();
+                hasher.update(prefix);
+                ($name(hasher), &[])
+            }
+
+            fn finalize(&self, tail: &[u8]) -> [u8; 32] {
+                let mut hasher = self.0.clone();
+                hasher.update(tail);
+                hasher.finalize().into()
+            }
+        }
+    };
+}
+
+digest_pow_hash!(
+    /// SHA3-256（FIPS 202）
+    Sha3_256, sha3::Sha3_256
+);
+digest_pow_hash!(
+    /// Keccak-256（以太坊使用的原始 Keccak 填充）
+    Keccak256, sha3::Keccak256
+);
+digest_pow_hash!(
+    /// 输出 256 位的 BLAKE2b
+    Blake2b, blake2::Blake2b<U32>
+);
+
+/// BLAKE3
+#[derive(Debug, Clone)]
+pub struct Blake3(blake3::Hasher);
+
+impl PowHash for Blake3 {
+    fn absorb(prefix: &[u8]) -> (Self, &[u8]) {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(prefix);
+        (Blake3(hasher), &[])
+    }
+
+    fn finalize(&self, tail: &[u8]) -> [u8; 32] {
+        let mut hasher = self.0.clone();
+        hasher.update(tail);
+        hasher.finalize().into()
+    }
+}
+
+/// 可选的哈希算法，用于在运行时选择 [`PowHash`] 的实现
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Algorithm {
+    #[default]
+    Sha256,
+    Sha256d,
+    Sha3_256,
+    Keccak256,
+    Blake2b,
+    Blake3,
+}
+
+impl Algorithm {
+    /// 全部算法
+    pub const ALL: [Algorithm; 6] = [
+        Algorithm::Sha256,
+        Algorithm::Sha256d,
+        Algorithm::Sha3_256,
+        Algorithm::Keccak256,
+        Algorithm::Blake2b,
+        Algorithm::Blake3,
+    ];
+
+    /// 命令行和检查点中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Sha256d => "sha256d",
+            Algorithm::Sha3_256 => "sha3-256",
+            Algorithm::Keccak256 => "keccak256",
+            Algorithm::Blake2b => "blake2b",
+            Algorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// SHA-256 系列算法在本机使用的 nonce 分组实现，其它算法返回 `None`
+    pub fn backend(&self) -> Option<Backend> {
+        match self {
+            Algorithm::Sha256 | Algorithm::Sha256d => Some(Backend::detect()),
+            _ => None,
+        }
+    }
+
+    /// 计算完整输入的摘要
+    pub fn digest(&self, input: &[u8]) -> [u8; 32] {
+        match self {
+            Algorithm::Sha256 => Sha256::digest(input),
+            Algorithm::Sha256d => Sha256d::digest(input),
+            Algorithm::Sha3_256 => Sha3_256::digest(input),
+            Algorithm::Keccak256 => Keccak256::digest(input),
+            Algorithm::Blake2b => Blake2b::digest(input),
+            Algorithm::Blake3 => Blake3::digest(input),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Algorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Algorithm::ALL.iter().map(Algorithm::name).collect();
+                format!("未知的哈希算法: {}（可用 {}）", s, names.join("、"))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 各算法对 "abc" 的公开测试向量
+    const ABC: [(Algorithm, &str); 6] = [
+        (Algorithm::Sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+        (Algorithm::Sha256d, "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358"),
+        (Algorithm::Sha3_256, "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"),
+        (Algorithm::Keccak256, "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"),
+        (Algorithm::Blake2b, "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319"),
+        (Algorithm::Blake3, "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"),
+    ];
+
+    /// 参考实现直接计算的摘要
+    fn reference(algorithm: Algorithm, input: &[u8]) -> [u8; 32] {
+        match algorithm {
+            Algorithm::Sha256 => sha2::Sha256::digest(input).into(),
+            Algorithm::Sha256d => sha2::Sha256::digest(sha2::Sha256::digest(input)).into(),
+            Algorithm::Sha3_256 => sha3::Sha3_256::digest(input).into(),
+            Algorithm::Keccak256 => sha3::Keccak256::digest(input).into(),
+            Algorithm::Blake2b => blake2::Blake2b::<U32>::digest(input).into(),
+            Algorithm::Blake3 => blake3::hash(input).into(),
+        }
+    }
+
+    #[test]
+    fn known_answers() {
+        assert_eq!(ABC.len(), Algorithm::ALL.len());
+        for (algorithm, expected) in ABC {
+            assert_eq!(hex::encode(algorithm.digest(b"abc")), expected, "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn digest_matches_reference() {
+        for algorithm in Algorithm::ALL {
+            for len in [0, 1, 55, 56, 64, 100, 200] {
+                let input: Vec<u8> = (0..len).map(|i| (i * 11 + 7) as u8).collect();
+                assert_eq!(algorithm.digest(&input), reference(algorithm, &input), "{} {} 字节", algorithm, len);
+            }
+        }
+    }
+
+    /// 先吸收前缀再逐个或并行计算nonce部分，结果与完整输入的摘要一致
+    fn assert_lanes_match<H: PowHash>(algorithm: Algorithm) {
+        for prefix_len in [0, 9, 64, 100] {
+            let prefix: Vec<u8> = (0..prefix_len).map(|i| (i * 3 + 1) as u8).collect();
+            let (state, rest) = H::absorb(&prefix);
+            let lanes = state.lanes();
+
+            for nonce_len in [1, 8, 20, 70] {
+                let tails: Vec<Vec<u8>> = (0..lanes)
+                    .map(|lane| [rest, &vec![b'0' + lane as u8; nonce_len]].concat())
+                    .collect();
+                let refs: Vec<&[u8]> = tails.iter().map(Vec::as_slice).collect();
+                let mut digests = [[0u8; 32]; MAX_LANES];
+                state.finalize_lanes(&refs, &mut digests[..lanes]);
+
+                for (tail, digest) in tails.iter().zip(&digests) {
+                    let input = [&prefix[..prefix.len() - rest.len()], tail].concat();
+                    assert_eq!(state.finalize(tail), reference(algorithm, &input));
+                    assert_eq!(*digest, reference(algorithm, &input), "{} 前缀 {} 字节", algorithm, prefix_len);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn finalize_lanes_matches_reference() {
+        assert_lanes_match::<Sha256>(Algorithm::Sha256);
+        assert_lanes_match::<Sha256d>(Algorithm::Sha256d);
+        assert_lanes_match::<Sha3_256>(Algorithm::Sha3_256);
+        assert_lanes_match::<Keccak256>(Algorithm::Keccak256);
+        assert_lanes_match::<Blake2b>(Algorithm::Blake2b);
+        assert_lanes_match::<Blake3>(Algorithm::Blake3);
+    }
+
+    #[test]
+    fn sha256d_lanes_on_every_backend() {
+        // 第二轮的 32 字节输入也走多通道路径
+        let prefix = b"weimeityy";
+        for backend in [Backend::Scalar, Backend::ShaNi, Backend::Sse41, Backend::Avx2] {
+            if !backend.is_supported() {
+                continue;
+            }
+            let (midstate, rest) = Midstate::new(prefix);
+            let state = Sha256d { midstate, backend };
+            let tails: Vec<Vec<u8>> = (0..backend.lanes())
+                .map(|lane| [rest, (88_493 + lane).to_string().as_bytes()].concat())
+                .collect();
+            let refs: Vec<&[u8]> = tails.iter().map(Vec::as_slice).collect();
+            let mut digests = [[0u8; 32]; MAX_LANES];
+            state.finalize_lanes(&refs, &mut digests[..backend.lanes()]);
+
+            for (tail, digest) in tails.iter().zip(&digests) {
+                assert_eq!(*digest, reference(Algorithm::Sha256d, tail), "{}", backend);
+            }
+        }
+    }
+
+    #[test]
+    fn name_round_trip() {
+        for algorithm in Algorithm::ALL {
+            assert_eq!(algorithm.name().parse::<Algorithm>(), Ok(algorithm));
+            assert_eq!(algorithm.to_string(), algorithm.name());
+        }
+        assert!("sha512".parse::<Algorithm>().unwrap_err().contains("sha512"));
+    }
+}
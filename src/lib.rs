@@ -0,0 +1,21 @@
+//! 并行工作量证明（POW）挖矿库，默认使用 SHA-256，也支持其他哈希算法
+
+pub mod checkpoint;
+pub mod difficulty;
+pub mod encoding;
+pub mod hash;
+pub mod miner;
+pub mod schedule;
+pub mod search;
+pub mod sha256;
+pub mod simd;
+pub mod verify;
+
+pub use checkpoint::Checkpoint;
+pub use difficulty::{ByteOrder, Difficulty, Target};
+pub use encoding::{Alphabet, NonceEncoding};
+pub use hash::{Algorithm, PowHash};
+pub use miner::{BestHash, BuildError, Event, Milestone, Miner, MiningJob, Outcome, Progress, Report, Solution, StopHandle};
+pub use schedule::RangeSet;
+pub use search::Searcher;
+pub use verify::{verify, verify_with, Verification};
@@ -1,155 +1,944 @@
-use std::sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}};
-use std::time::{Instant, Duration};
-use sha2::{Sha256, Digest};
-use std::sync::mpsc;
-use std::thread;
-use clap::Parser;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use pow_rs::{
+    Algorithm, Alphabet, BestHash, ByteOrder, Checkpoint, Difficulty, Event, Milestone, Miner, MiningJob,
+    NonceEncoding, Outcome, Progress, Report, Solution, Target,
+};
+use pow_rs::simd::Backend;
+use serde_json::{json, Value};
+
+/// 搜索完整个nonce范围仍未找到结果时的退出码
+const EXIT_NOT_FOUND: u8 = 3;
+
+/// 被 Ctrl-C 或 SIGTERM 中断时的退出码
+const EXIT_INTERRUPTED: u8 = 130;
+
+/// 达到 --timeout 或 --max-hashes 上限时的退出码
+const EXIT_BUDGET: u8 = 4;
+
+/// 预计耗时超过该值且没有设置运行上限时，需要 --force 才开始挖矿
+const FORCE_THRESHOLD: Duration = Duration::from_secs(3600);
+
+/// 开始前测量哈希速率时每个样本的哈希次数
+const CALIBRATION_HASHES: u64 = 200_000;
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    mine: MineArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 验证 prefix + nonce 是否满足难度要求
+    Verify(VerifyArgs),
+    /// 在十六进制零、前导零比特、目标值和 nBits 之间换算，并估算耗时
+    Difficulty(CalcArgs),
+    /// 用不可能满足的目标测量不同线程数和前缀长度下的哈希速率
+    Bench(BenchArgs),
+}
+
+#[derive(Args, Debug)]
+struct MineArgs {
     /// 固定字符串前缀
     #[arg(short, long, default_value = "weimeityy")]
     prefix: String,
 
+    #[command(flatten)]
+    difficulty: DifficultyArgs,
+
+    /// 哈希算法: sha256、sha256d、sha3-256、keccak256、blake2b、blake3
+    #[arg(long, default_value = "sha256", value_parser = Algorithm::from_str)]
+    algo: Algorithm,
+
+    #[command(flatten)]
+    nonce: NonceArgs,
+
+    /// 保证返回满足条件的最小nonce，结果与线程数量无关
+    #[arg(long)]
+    lowest: bool,
+
+    /// 搜索的起始nonce（包含）
+    #[arg(long, default_value_t = 0)]
+    start: u64,
+
+    /// 搜索的结束nonce（包含）
+    #[arg(long, default_value_t = u64::MAX)]
+    end: u64,
+
+    /// 工作线程数量，默认使用全部CPU核心
+    #[arg(short, long)]
+    threads: Option<NonZeroUsize>,
+
+    /// 找到 N 个满足条件的nonce后停止，每找到一个立即输出
+    #[arg(short, long, value_name = "N", default_value = "1")]
+    count: NonZeroU64,
+
+    /// 找出范围内全部满足条件的nonce
+    #[arg(long, conflicts_with = "count")]
+    all: bool,
+
+    /// 输出格式
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// 定期把参数和已搜索的nonce区间保存到该文件
+    #[arg(long, value_name = "FILE")]
+    checkpoint: Option<PathBuf>,
+
+    /// 检查点保存间隔（秒）
+    #[arg(long, value_name = "SECS", default_value_t = 30)]
+    checkpoint_interval: u64,
+
+    /// 运行时间上限，例如 90、30s、500ms、10m、2h（不带单位时为秒）
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    timeout: Option<Duration>,
+
+    /// 哈希次数上限
+    #[arg(long, value_name = "N")]
+    max_hashes: Option<NonZeroU64>,
+
+    /// 不设难度目标，在 --timeout、--max-hashes 或 --end 限定的范围内寻找数值最小的哈希
+    #[arg(long, conflicts_with_all = ["difficulty", "difficulty_bits", "target", "nbits", "lowest",
+                                      "count", "all"])]
+    anytime: bool,
+
+    /// 难度阶梯：搜索到 N 个十六进制前导零为止，记录达到每个前导零比特数的最小nonce
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..=64),
+          conflicts_with_all = ["difficulty", "difficulty_bits", "target", "nbits", "lowest",
+                                "count", "all", "anytime"])]
+    ladder: Option<u32>,
+
+    /// 预计耗时超过一小时也直接开始挖矿
+    #[arg(long)]
+    force: bool,
+
+    /// 从检查点文件继续搜索，跳过已搜索的nonce；默认继续保存到同一文件
+    #[arg(long, value_name = "FILE",
+          conflicts_with_all = ["prefix", "difficulty", "difficulty_bits", "target", "nbits", "lowest",
+                                "start", "end", "count", "all", "anytime", "ladder", "algo", "nonce_encoding",
+                                "nonce_alphabet", "nonce_length"])]
+    resume: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct VerifyArgs {
+    /// 固定字符串前缀
+    #[arg(short, long, default_value = "weimeityy")]
+    prefix: String,
+
+    /// 待验证的nonce；字符集nonce时为字符串的序号
+    #[arg(short, long, required_unless_present = "nonce_string")]
+    nonce: Option<u64>,
+
+    /// 直接给出字符集nonce的字符串，例如 ALAS，换算为序号后验证
+    #[arg(long, value_name = "STRING", conflicts_with = "nonce")]
+    nonce_string: Option<String>,
+
+    #[command(flatten)]
+    difficulty: DifficultyArgs,
+
+    /// 哈希算法: sha256、sha256d、sha3-256、keccak256、blake2b、blake3
+    #[arg(long, default_value = "sha256", value_parser = Algorithm::from_str)]
+    algo: Algorithm,
+
+    #[command(flatten)]
+    encoding: NonceArgs,
+}
+
+#[derive(Args, Debug)]
+struct CalcArgs {
+    #[command(flatten)]
+    difficulty: DifficultyArgs,
+
+    /// 按期望哈希次数指定难度，例如 1e9；恰好为 2 的整数次幂时换算为前导零比特
+    #[arg(long, value_name = "N", value_parser = parse_expected_hashes,
+          conflicts_with_all = ["difficulty", "difficulty_bits", "target", "nbits"])]
+    hashes: Option<f64>,
+
+    /// 哈希速率（哈希/秒），默认在本机用全部CPU核心测量
+    #[arg(long, value_name = "H/S", value_parser = parse_rate)]
+    rate: Option<f64>,
+
+    /// 测量哈希速率时使用的算法
+    #[arg(long, default_value = "sha256", value_parser = Algorithm::from_str, conflicts_with = "rate")]
+    algo: Algorithm,
+}
+
+#[derive(Args, Debug)]
+struct BenchArgs {
+    /// 每个配置的运行时间，例如 3s、500ms
+    #[arg(long, value_name = "DURATION", default_value = "3s", value_parser = parse_duration)]
+    duration: Duration,
+
+    /// 每个配置改为固定哈希次数
+    #[arg(long, value_name = "N", conflicts_with = "duration")]
+    hashes: Option<NonZeroU64>,
+
+    /// 要测试的线程数量（逗号分隔），默认为 1 以及 2 的幂直到全部CPU核心
+    #[arg(short, long, value_delimiter = ',')]
+    threads: Vec<NonZeroUsize>,
+
+    /// 要测试的前缀长度（字节，逗号分隔）
+    #[arg(long, value_delimiter = ',', default_value = "9,64,256")]
+    prefix_lengths: Vec<usize>,
+
+    /// 要测试的哈希算法（逗号分隔）
+    #[arg(long, value_delimiter = ',', default_value = "sha256", value_parser = Algorithm::from_str)]
+    algo: Vec<Algorithm>,
+
+    /// 输出格式
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Args, Debug)]
+struct DifficultyArgs {
     /// 需要的十六进制前导零数量
-    #[arg(short, long, default_value_t = 6)]
-    difficulty: usize,
+    #[arg(short, long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(0..=64))]
+    difficulty: u32,
+
+    /// 需要的前导零比特数量，每 4 比特相当于一个十六进制零
+    #[arg(short = 'b', long, conflicts_with = "difficulty",
+          value_parser = clap::value_parser!(u32).range(0..=256))]
+    difficulty_bits: Option<u32>,
+
+    /// 256 位目标值（十六进制），哈希值作为整数不大于该值即满足要求
+    #[arg(long, conflicts_with_all = ["difficulty", "difficulty_bits"], value_parser = parse_target)]
+    target: Option<Target>,
+
+    /// 比特币紧凑格式的目标值（十六进制 nBits，例如 1d00ffff）
+    #[arg(long, conflicts_with_all = ["difficulty", "difficulty_bits", "target"],
+          value_parser = parse_nbits)]
+    nbits: Option<Target>,
+
+    /// 与目标值比较时哈希值的字节序
+    #[arg(long, value_enum, default_value_t = TargetOrder::Big)]
+    target_order: TargetOrder,
+}
+
+#[derive(Args, Debug)]
+struct NonceArgs {
+    /// nonce 的编码: decimal、decimal-padded:N（补零到 N 位）、hex、le64、be64（8 字节二进制）
+    #[arg(long, value_name = "ENCODING", default_value = "decimal", value_parser = NonceEncoding::from_str)]
+    nonce_encoding: NonceEncoding,
+
+    /// 以该字符集上的字符串作为nonce，例如 A-Za-z0-9（`-` 在开头或结尾时表示它本身）
+    #[arg(long, value_name = "CHARS", conflicts_with = "nonce_encoding", requires = "nonce_length")]
+    nonce_alphabet: Option<String>,
+
+    /// 字符串nonce的长度范围，例如 4-8 或 6
+    #[arg(long, value_name = "MIN-MAX", requires = "nonce_alphabet", value_parser = parse_length_range)]
+    nonce_length: Option<(u32, u32)>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    /// 人类可读的文本
+    Text,
+    /// 结束时输出一个 JSON 对象
+    Json,
+    /// 每行一个 JSON 事件（开始、进度、结果）
+    Jsonl,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum TargetOrder {
+    /// 哈希值第一个字节为最高位
+    Big,
+    /// 哈希值最后一个字节为最高位（比特币区块哈希）
+    Little,
+}
+
+impl DifficultyArgs {
+    fn resolve(&self) -> Difficulty {
+        let order = match self.target_order {
+            TargetOrder::Big => ByteOrder::BigEndian,
+            TargetOrder::Little => ByteOrder::LittleEndian,
+        };
+
+        if let Some(target) = self.target.or(self.nbits) {
+            Difficulty::from_target(target.with_byte_order(order))
+        } else if let Some(bits) = self.difficulty_bits {
+            Difficulty::from_bits(bits)
+        } else {
+            Difficulty::from_hex_zeros(self.difficulty)
+        }
+    }
+}
+
+impl NonceArgs {
+    fn resolve(&self) -> NonceEncoding {
+        let (Some(chars), Some((min_len, max_len))) = (&self.nonce_alphabet, self.nonce_length) else {
+            return self.nonce_encoding.clone();
+        };
+        match Alphabet::parse_set(chars).and_then(|chars| Alphabet::new(chars, min_len, max_len)) {
+            Ok(alphabet) => NonceEncoding::Alphabet(alphabet),
+            Err(e) => Cli::command().error(ErrorKind::ValueValidation, e).exit(),
+        }
+    }
+}
+
+fn parse_length_range(s: &str) -> Result<(u32, u32), String> {
+    let (min, max) = s.split_once('-').unwrap_or((s, s));
+    match (min.parse(), max.parse()) {
+        (Ok(min), Ok(max)) => Ok((min, max)),
+        _ => Err(format!("无效的长度范围: {}", s)),
+    }
+}
+
+fn parse_target(s: &str) -> Result<Target, String> {
+    Target::from_hex(s).map_err(|e| e.to_string())
+}
+
+fn parse_nbits(s: &str) -> Result<Target, String> {
+    let nbits = u32::from_str_radix(s.strip_prefix("0x").unwrap_or(s), 16)
+        .map_err(|e| e.to_string())?;
+    Target::from_compact(nbits).map_err(|e| e.to_string())
+}
+
+fn parse_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 => Ok(rate),
+        _ => Err(format!("无效的哈希速率: {}", s)),
+    }
 }
 
-fn main() {
+fn parse_expected_hashes(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(hashes) if (1.0..=2f64.powi(256)).contains(&hashes) => Ok(hashes),
+        _ => Err(format!("期望哈希次数必须在 1 到 2^256 之间: {}", s)),
+    }
+}
+
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let split = s.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().map_err(|_| format!("无效的时长: {}", s))?;
+    let secs = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return Err(format!("未知的时间单位: {}（可用 ms、s、m、h）", unit)),
+    };
+    Duration::try_from_secs_f64(secs).map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
     // 解析命令行参数
-    let args = Args::parse();
-    let prefix = args.prefix;
-    let difficulty = args.difficulty;
-    
-    println!("开始POW挖矿，前缀: {}, 难度: {} 个十六进制前导零", prefix, difficulty);
-    let start_time = Instant::now();
-    
-    // 用于发送找到的nonce
-    let (sender, receiver) = mpsc::channel();
-    // 用于通知其他线程停止工作
-    let found = Arc::new(AtomicBool::new(false));
-    // 用于统计已尝试的哈希次数
-    let hash_count = Arc::new(AtomicU64::new(0));
-    
-    // 获取可用的CPU核心数量
-    let num_threads = rayon::current_num_threads();
-    println!("使用 {} 个线程进行挖矿", num_threads);
-    
-    // 每个线程负责不同范围的nonce
-    let chunk_size = u64::MAX / num_threads as u64;
-    
-    let report_hash_count = hash_count.clone();
-    let report_found = found.clone();
-    let report_handle = thread::spawn(move || {
-        let mut last_count = 0u64;
-        let mut last_time = Instant::now();
-        
-        while !report_found.load(Ordering::Relaxed) {
-            thread::sleep(Duration::from_secs(1));
-            let current_count = report_hash_count.load(Ordering::Relaxed);
-            let current_time = Instant::now();
-            let elapsed = current_time.duration_since(last_time).as_secs_f64();
-            let hashes_per_second = (current_count - last_count) as f64 / elapsed;
-            
-            println!("当前速率: {:.2} 哈希/秒，总计尝试: {} 哈希", 
-                     hashes_per_second, current_count);
-            
-            last_count = current_count;
-            last_time = current_time;
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Verify(args)) => verify(args),
+        Some(Command::Difficulty(args)) => calculate(args),
+        Some(Command::Bench(args)) => bench(args),
+        None => mine(cli.mine),
+    }
+}
+
+fn mine(args: MineArgs) -> ExitCode {
+    let encoding = args.nonce.resolve();
+    let max_nonce = encoding.max_nonce();
+    // 构建任务时终点会截断到编码的最大值，但显式给出的终点超出时更可能是写错了
+    if args.end != u64::MAX && args.end > max_nonce {
+        Cli::command()
+            .error(ErrorKind::ValueValidation, format!("nonce编码 {} 最大只能表示 {}", encoding, max_nonce))
+            .exit();
+    }
+    if args.anytime && args.timeout.is_none() && args.max_hashes.is_none() && args.end.min(max_nonce) == u64::MAX {
+        Cli::command()
+            .error(ErrorKind::MissingRequiredArgument, "--anytime 需要 --timeout、--max-hashes 或 --end 之一")
+            .exit();
+    }
+
+    let miner = match &args.resume {
+        Some(path) => match Checkpoint::load(path) {
+            Ok(checkpoint) => Miner::resume(checkpoint),
+            Err(e) => {
+                eprintln!("无法读取检查点 {}: {}", path.display(), e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None if args.anytime => Miner::new(args.prefix)
+            .anytime()
+            .algorithm(args.algo)
+            .nonce_encoding(encoding.clone())
+            .range(args.start, args.end),
+        None if args.ladder.is_some() => Miner::new(args.prefix)
+            .ladder(args.ladder.unwrap_or_default() * 4)
+            .algorithm(args.algo)
+            .nonce_encoding(encoding.clone())
+            .range(args.start, args.end),
+        None => Miner::new(args.prefix)
+            .difficulty(args.difficulty.resolve())
+            .algorithm(args.algo)
+            .nonce_encoding(encoding.clone())
+            .range(args.start, args.end)
+            .lowest_nonce(args.lowest)
+            .solutions((!args.all).then_some(args.count.get())),
+    };
+
+    let mut miner = miner.checkpoint_interval(Duration::from_secs(args.checkpoint_interval));
+    if let Some(path) = args.checkpoint.or(args.resume) {
+        miner = miner.checkpoint(path);
+    }
+    if let Some(threads) = args.threads {
+        miner = miner.threads(threads.get());
+    }
+    if let Some(timeout) = args.timeout {
+        miner = miner.timeout(timeout);
+    }
+    if let Some(max_hashes) = args.max_hashes {
+        miner = miner.max_hashes(max_hashes.get());
+    }
+    let job = match miner.build() {
+        Ok(job) => job,
+        Err(e) => Cli::command().error(ErrorKind::ValueValidation, e).exit(),
+    };
+    let format = args.format;
+
+    // 没有运行上限时先估算耗时，避免不小心开始一个几年都跑不完的任务
+    let bounded = job.timeout().is_some() || job.max_hashes().is_some();
+    let (start, end) = job.range();
+    let pending = job.covered().complement_within(start, end).count() as f64;
+    let estimate = job
+        .expected_hashes()
+        .map(|expected| expected.min(pending) / measure_hash_rate(job.prefix(), job.algorithm(), job.threads()));
+    if let (false, false, Some(secs)) = (args.force, bounded, estimate)
+        && secs > FORCE_THRESHOLD.as_secs_f64()
+    {
+        eprintln!("预计耗时约 {}，超过 {}；确认要开始请加上 --force，或用 --timeout / --max-hashes 限制运行时间",
+                  format_secs(secs), format_secs(FORCE_THRESHOLD.as_secs_f64()));
+        return ExitCode::FAILURE;
+    }
+
+    match format {
+        OutputFormat::Text => print_banner(&job, estimate),
+        OutputFormat::Jsonl => emit(json!({
+            "event": "start",
+            "prefix": job.prefix(),
+            "difficulty": difficulty_json(&job.difficulty()),
+            "algorithm": job.algorithm().name(),
+            "backend": job.algorithm().backend().map(Backend::name),
+            "nonce_encoding": job.nonce_encoding().to_string(),
+            "anytime": job.is_anytime(),
+            "ladder": job.is_ladder(),
+            "start": job.range().0,
+            "end": job.range().1,
+            "threads": job.threads(),
+            "lowest_nonce": job.lowest_nonce(),
+            "count": job.count(),
+            "skipped": u64::try_from(job.covered().count()).unwrap_or(u64::MAX),
+            "previous_solutions": job.found(),
+            "timeout": job.timeout().map(|timeout| timeout.as_secs_f64()),
+            "max_hashes": job.max_hashes(),
+            "expected_hashes": job.expected_hashes(),
+            "expected_seconds": estimate,
+        })),
+        OutputFormat::Json => {}
+    }
+    let single = job.count() == Some(1);
+
+    // 第一次收到 Ctrl-C / SIGTERM 时让工作线程汇总计数后退出，再次收到时立即退出
+    let stop = job.stop_handle();
+    let handler = ctrlc::set_handler(move || {
+        if stop.is_stopped() {
+            std::process::exit(EXIT_INTERRUPTED.into());
         }
+        eprintln!("\n收到停止信号，正在等待工作线程退出...");
+        stop.stop();
     });
-    
-    // 创建线程池并行处理
-    rayon::scope(|s| {
-        for i in 0..num_threads {
-            let sender = sender.clone();
-            let found = found.clone();
-            let hash_count = hash_count.clone();
-            let prefix = prefix.clone();
-            let start = i as u64 * chunk_size;
-            let end = if i == num_threads - 1 {
-                u64::MAX
-            } else {
-                (i as u64 + 1) * chunk_size - 1
-            };
-            
-            s.spawn(move |_| {
-                mine_range(&prefix, difficulty, start, end, sender, found, hash_count);
-            });
+    if let Err(e) = handler {
+        eprintln!("无法安装信号处理函数: {}", e);
+    }
+
+    let mut found = 0u64;
+    let report = job.run_with_events(|event| match (format, event) {
+        (_, Event::CheckpointFailed(error)) => eprintln!("保存检查点失败: {}", error),
+        (OutputFormat::Text, Event::Progress(progress)) => {
+            print!("当前速率: {:.2} 哈希/秒，总计尝试: {} 哈希",
+                   progress.hashes_per_second, progress.total_hashes);
+            if let Some(probability) = job.success_probability(progress.total_hashes) {
+                print!("，已找到的概率: {:.1}%", probability * 100.0);
+            }
+            if let Some(eta) = eta(&job, &progress) {
+                print!("，预计剩余: {}", format_secs(eta));
+            }
+            match &progress.best {
+                Some(best) => println!("，最佳: {}（nonce: {}，{} 比特）",
+                                       best.hash, best.nonce, best.leading_zero_bits),
+                None => println!(),
+            }
+        }
+        (OutputFormat::Text, Event::Milestone(milestone)) => {
+            println!("达到 {} 个前导零比特: nonce {}，哈希值 {}，尝试 {} 哈希",
+                     milestone.bits, milestone.nonce, milestone.hash, milestone.hashes_tried);
+        }
+        (OutputFormat::Text, Event::Solution(solution)) if !single => {
+            found += 1;
+            println!("找到第 {} 个满足条件的nonce: {}，哈希值: {}", found, solution.nonce, solution.hash);
         }
+        (OutputFormat::Jsonl, Event::Progress(progress)) => emit(json!({
+            "event": "progress",
+            "hashes": progress.total_hashes,
+            "elapsed": progress.elapsed.as_secs_f64(),
+            "rate": progress.hashes_per_second,
+            "probability": job.success_probability(progress.total_hashes),
+            "eta": eta(&job, &progress),
+            "best": progress.best.as_ref().map(|best| best_json(&job, best)),
+        })),
+        (OutputFormat::Jsonl, Event::Milestone(milestone)) => {
+            let mut event = milestone_json(&job, &milestone);
+            event["event"] = json!("milestone");
+            emit(event);
+        }
+        (OutputFormat::Jsonl, Event::Solution(solution)) => {
+            let mut event = solution_json(&job, &solution);
+            event["event"] = json!("solution");
+            emit(event);
+        }
+        _ => {}
     });
-    
-    // 主线程接收结果
-    if let Ok((nonce, hash)) = receiver.recv() {
-        let duration = start_time.elapsed();
-        let total_hashes = hash_count.load(Ordering::Relaxed);
-        
-        println!("\n找到满足条件的nonce: {}", nonce);
-        println!("对应的哈希值: {}", hash);
-        println!("耗时: {:.2?}", duration);
-        println!("总计尝试: {} 哈希", total_hashes);
-        println!("平均哈希速率: {:.2} 哈希/秒", total_hashes as f64 / duration.as_secs_f64());
-        
-        // 显示组合字符串
-        println!("组合字符串: {}{}", prefix, nonce);
-    }
-    
-    // 等待报告线程结束
-    let _ = report_handle.join();
-}
-
-fn mine_range(
-    prefix: &str, 
-    difficulty: usize, 
-    start: u64, 
-    end: u64, 
-    sender: mpsc::Sender<(u64, String)>, 
-    found: Arc<AtomicBool>,
-    hash_count: Arc<AtomicU64>
-) {
-    let target_prefix = "0".repeat(difficulty);
-    
-    let mut nonce = start;
-    let mut hasher = Sha256::new();
-    let mut local_hash_count = 0u64;
-    
-    while nonce <= end && !found.load(Ordering::Relaxed) {
-        hasher.reset();
-        
-        // 计算 prefix + nonce 的哈希值
-        let data = format!("{}{}", prefix, nonce);
-        hasher.update(data.as_bytes());
-        
-        // 获取哈希结果
-        let result = hasher.finalize_reset();
-        let hash_hex = hex::encode(result);
-        
-        // 检查前导零
-        if hash_hex.starts_with(&target_prefix) {
-            // 找到符合条件的nonce
-            found.store(true, Ordering::Relaxed);
-            let _ = sender.send((nonce, hash_hex));
-            break;
-        }
-        
-        nonce += 1;
-        local_hash_count += 1;
-        
-        // 周期性更新计数和检查是否已经找到结果
-        if local_hash_count % 100_000 == 0 {
-            hash_count.fetch_add(local_hash_count, Ordering::Relaxed);
-            local_hash_count = 0;
-            
-            if found.load(Ordering::Relaxed) {
-                break;
+
+    match format {
+        OutputFormat::Text => print_report(&job, &report),
+        OutputFormat::Json | OutputFormat::Jsonl => emit(report_json(&job, &report)),
+    }
+
+    match report.outcome {
+        Outcome::Found => ExitCode::SUCCESS,
+        Outcome::Interrupted => ExitCode::from(EXIT_INTERRUPTED),
+        // anytime 模式下用完预算是正常结束，结果为见过的最佳哈希
+        _ if job.is_anytime() && report.best.is_some() => ExitCode::SUCCESS,
+        Outcome::NotFound => ExitCode::from(EXIT_NOT_FOUND),
+        Outcome::Timeout | Outcome::HashLimit => ExitCode::from(EXIT_BUDGET),
+    }
+}
+
+/// 单线程搜索一小段nonce测量速率，再乘以线程数
+fn measure_hash_rate(prefix: &str, algorithm: Algorithm, threads: usize) -> f64 {
+    let report = Miner::new(prefix)
+        .anytime()
+        .algorithm(algorithm)
+        .threads(1)
+        .max_hashes(CALIBRATION_HASHES)
+        .build()
+        .expect("默认nonce范围总是有效")
+        .run();
+    report.hashes_per_second() * threads as f64
+}
+
+/// 按还需要的结果数量估算的剩余秒数
+fn eta(job: &MiningJob, progress: &Progress) -> Option<f64> {
+    let remaining = job.remaining_hashes(progress.solutions)?;
+    (remaining > 0.0 && progress.hashes_per_second > 0.0).then(|| remaining / progress.hashes_per_second)
+}
+
+/// 把秒数格式化为便于阅读的时长
+fn format_secs(secs: f64) -> String {
+    const MINUTE: f64 = 60.0;
+    const HOUR: f64 = 60.0 * MINUTE;
+    const DAY: f64 = 24.0 * HOUR;
+    const YEAR: f64 = 365.0 * DAY;
+
+    if secs < MINUTE {
+        format!("{:.1} 秒", secs)
+    } else if secs < HOUR {
+        format!("{:.1} 分钟", secs / MINUTE)
+    } else if secs < DAY {
+        format!("{:.1} 小时", secs / HOUR)
+    } else if secs < YEAR {
+        format!("{:.1} 天", secs / DAY)
+    } else {
+        format!("{:.3e} 年", secs / YEAR)
+    }
+}
+
+fn print_banner(job: &MiningJob, estimate: Option<f64>) {
+    if job.is_anytime() {
+        println!("开始POW挖矿，前缀: {}, 不设难度目标，寻找数值最小的哈希", job.prefix());
+    } else {
+        println!("开始POW挖矿，前缀: {}, 难度: {}", job.prefix(), job.difficulty());
+    }
+    if job.algorithm() != Algorithm::Sha256 {
+        println!("哈希算法: {}", job.algorithm());
+    }
+    match job.nonce_encoding() {
+        NonceEncoding::Decimal => {}
+        NonceEncoding::Alphabet(alphabet) => {
+            let count = alphabet.count().map_or_else(|| "超过 2^128".to_string(), |count| count.to_string());
+            println!("nonce字符集: {}（{} 个字符），长度 {}-{}，共 {} 个字符串",
+                     String::from_utf8_lossy(alphabet.chars()), alphabet.chars().len(),
+                     alphabet.min_len(), alphabet.max_len(), count);
+        }
+        encoding => println!("nonce编码: {}", encoding),
+    }
+    if let Some(backend) = job.algorithm().backend() {
+        println!("SHA-256 实现: {}", backend);
+    }
+    if job.range() != (0, u64::MAX) {
+        println!("nonce范围: {} - {}", job.range().0, job.range().1);
+    }
+    if !job.covered().is_empty() {
+        println!("从检查点继续，跳过已搜索的 {} 个nonce", job.covered().count());
+    }
+    if !job.found().is_empty() {
+        println!("检查点中已找到 {} 个满足条件的nonce，计入结果数量", job.found().len());
+    }
+    if job.is_ladder() {
+        println!("难度阶梯模式: 记录达到每个前导零比特数的最小nonce");
+    }
+    if job.lowest_nonce() {
+        println!("最小nonce模式: 搜索完所有更小的nonce后才报告结果");
+    }
+    match job.count() {
+        Some(1) => {}
+        Some(count) => println!("需要找到 {} 个满足条件的nonce", count),
+        None => println!("找出范围内全部满足条件的nonce"),
+    }
+    if let Some(timeout) = job.timeout() {
+        println!("运行时间上限: {:.2?}", timeout);
+    }
+    if let Some(max_hashes) = job.max_hashes() {
+        println!("哈希次数上限: {}", max_hashes);
+    }
+    if let Some(expected) = job.expected_hashes() {
+        match estimate {
+            Some(secs) => println!("期望哈希次数: {:.3e}，预计耗时约 {}", expected, format_secs(secs)),
+            None => println!("期望哈希次数: {:.3e}", expected),
+        }
+    }
+    println!("使用 {} 个线程进行挖矿", job.threads());
+}
+
+fn print_report(job: &MiningJob, report: &Report) {
+    if job.is_anytime() {
+        print_best_report(job, report);
+        return;
+    }
+    let single = job.count() == Some(1);
+
+    match report.outcome {
+        Outcome::Found => {
+            if let (true, Some(solution)) = (single, report.solution()) {
+                println!("\n找到满足条件的nonce: {}", solution.nonce);
+                print_encoded_nonce(job.nonce_encoding(), solution.nonce);
+                println!("对应的哈希值: {}", solution.hash);
+            } else {
+                println!("\n共找到 {} 个满足条件的nonce", report.solutions.len());
             }
         }
+        Outcome::Interrupted => {
+            println!("\n挖矿已中断，找到 {} 个满足条件的nonce", report.solutions.len());
+        }
+        Outcome::NotFound => {
+            println!("\n已搜索完整个nonce范围，只找到 {} 个满足条件的nonce", report.solutions.len());
+        }
+        Outcome::Timeout => {
+            println!("\n已达到运行时间上限，找到 {} 个满足条件的nonce", report.solutions.len());
+        }
+        Outcome::HashLimit => {
+            println!("\n已达到哈希次数上限，找到 {} 个满足条件的nonce", report.solutions.len());
+        }
+    }
+
+    if !single {
+        for solution in &report.solutions {
+            println!("  nonce: {}，哈希值: {}", solution.nonce, solution.hash);
+        }
+    }
+
+    println!("耗时: {:.2?}", report.elapsed);
+    println!("总计尝试: {} 哈希", report.total_hashes);
+    println!("平均哈希速率: {:.2} 哈希/秒", report.hashes_per_second());
+    if let Some(highest) = report.highest_nonce {
+        println!("已覆盖的最大nonce: {}", highest);
     }
-    
-    // 添加剩余的哈希计数
-    if local_hash_count > 0 {
-        hash_count.fetch_add(local_hash_count, Ordering::Relaxed);
+    if let Some(best) = &report.best {
+        println!("见过的最佳哈希: {}（nonce: {}，{} 个前导零比特）",
+                 best.hash, best.nonce, best.leading_zero_bits);
+    }
+
+    if job.is_ladder() {
+        print_ladder(&report.ladder);
+    }
+
+    if let (true, Some(solution)) = (single, report.solution()) {
+        print_input(&job.input(solution.nonce), job.nonce_encoding());
+    }
+}
+
+/// 难度阶梯的结果表
+fn print_ladder(ladder: &[Milestone]) {
+    println!("\n{:>6} {:>8} {:>20} {:>20}  哈希值", "比特", "十六进制", "nonce", "尝试哈希次数");
+    for milestone in ladder {
+        let hex_zeros = match milestone.bits % 4 {
+            0 => (milestone.bits / 4).to_string(),
+            _ => String::new(),
+        };
+        println!("{:>8} {:>12} {:>20} {:>26}  {}",
+                 milestone.bits, hex_zeros, milestone.nonce, milestone.hashes_tried, milestone.hash);
+    }
+}
+
+/// anytime 模式的结果：停止原因和见过的最佳哈希
+fn print_best_report(job: &MiningJob, report: &Report) {
+    match report.outcome {
+        Outcome::Timeout => println!("\n已达到运行时间上限"),
+        Outcome::HashLimit => println!("\n已达到哈希次数上限"),
+        Outcome::Interrupted => println!("\n挖矿已中断"),
+        Outcome::Found | Outcome::NotFound => println!("\n已搜索完整个nonce范围"),
+    }
+
+    if let Some(best) = &report.best {
+        println!("最佳nonce: {}", best.nonce);
+        print_encoded_nonce(job.nonce_encoding(), best.nonce);
+        println!("对应的哈希值: {}", best.hash);
+        println!("前导零数量: {} 比特", best.leading_zero_bits);
+    }
+    println!("耗时: {:.2?}", report.elapsed);
+    println!("总计尝试: {} 哈希", report.total_hashes);
+    println!("平均哈希速率: {:.2} 哈希/秒", report.hashes_per_second());
+    if let Some(best) = &report.best {
+        print_input(&job.input(best.nonce), job.nonce_encoding());
+    }
+}
+
+/// 非默认编码时显示nonce实际追加到前缀之后的内容
+fn print_encoded_nonce(encoding: &NonceEncoding, nonce: u64) {
+    match encoding {
+        NonceEncoding::Decimal => {}
+        NonceEncoding::Alphabet(_) => println!("nonce对应的字符串: {}", encoding.display(nonce)),
+        _ => println!("编码后的nonce（{}）: {}", encoding, encoding.display(nonce)),
+    }
+}
+
+/// 显示完整的哈希输入，二进制编码时以十六进制显示
+fn print_input(input: &[u8], encoding: &NonceEncoding) {
+    if encoding.is_binary() {
+        println!("组合输入（十六进制）: {}", hex::encode(input));
+    } else {
+        println!("组合字符串: {}", String::from_utf8_lossy(input));
+    }
+}
+
+/// 输出一行紧凑的 JSON
+fn emit(value: Value) {
+    println!("{}", value);
+}
+
+fn difficulty_json(difficulty: &Difficulty) -> Value {
+    match difficulty {
+        Difficulty::LeadingZeroBits(bits) => json!({ "type": "bits", "bits": bits }),
+        Difficulty::Target(target) => json!({
+            "type": "target",
+            "target": hex::encode(target.to_be_bytes()),
+            "byte_order": match target.byte_order() {
+                ByteOrder::BigEndian => "big",
+                ByteOrder::LittleEndian => "little",
+            },
+        }),
+    }
+}
+
+fn solution_json(job: &MiningJob, solution: &Solution) -> Value {
+    json!({
+        "nonce": solution.nonce,
+        "encoded_nonce": job.nonce_encoding().display(solution.nonce),
+        "hash": solution.hash,
+        "hashes": solution.hashes_tried,
+        "elapsed": solution.elapsed.as_secs_f64(),
+    })
+}
+
+fn milestone_json(job: &MiningJob, milestone: &Milestone) -> Value {
+    json!({
+        "bits": milestone.bits,
+        "nonce": milestone.nonce,
+        "encoded_nonce": job.nonce_encoding().display(milestone.nonce),
+        "hash": milestone.hash,
+        "hashes": milestone.hashes_tried,
+        "elapsed": milestone.elapsed.as_secs_f64(),
+    })
+}
+
+fn report_json(job: &MiningJob, report: &Report) -> Value {
+    let status = match report.outcome {
+        Outcome::Found => "found",
+        Outcome::Interrupted => "interrupted",
+        Outcome::NotFound => "not_found",
+        Outcome::Timeout => "timeout",
+        Outcome::HashLimit => "hash_limit",
+    };
+    let first = report.solution();
+
+    json!({
+        "event": "result",
+        "status": status,
+        "algorithm": job.algorithm().name(),
+        "nonce_encoding": job.nonce_encoding().to_string(),
+        "prefix": job.prefix(),
+        "difficulty": difficulty_json(&job.difficulty()),
+        "nonce": first.map(|solution| solution.nonce),
+        "encoded_nonce": first.map(|solution| job.nonce_encoding().display(solution.nonce)),
+        "hash": first.map(|solution| &solution.hash),
+        "solutions": report.solutions.iter().map(|solution| solution_json(job, solution)).collect::<Vec<_>>(),
+        "hashes": report.total_hashes,
+        "elapsed": report.elapsed.as_secs_f64(),
+        "rate": report.hashes_per_second(),
+        "highest_nonce": report.highest_nonce,
+        "anytime": job.is_anytime(),
+        "best": report.best.as_ref().map(|best| best_json(job, best)),
+        "ladder": report.ladder.iter().map(|milestone| milestone_json(job, milestone)).collect::<Vec<_>>(),
+    })
+}
+
+fn best_json(job: &MiningJob, best: &BestHash) -> Value {
+    json!({
+        "nonce": best.nonce,
+        "encoded_nonce": job.nonce_encoding().display(best.nonce),
+        "hash": best.hash,
+        "leading_zero_bits": best.leading_zero_bits,
+    })
+}
+
+fn verify(args: VerifyArgs) -> ExitCode {
+    let encoding = args.encoding.resolve();
+    let nonce = match (&args.nonce_string, &encoding) {
+        (Some(s), NonceEncoding::Alphabet(alphabet)) => match alphabet.index_of(s) {
+            Ok(nonce) => nonce,
+            Err(e) => Cli::command().error(ErrorKind::ValueValidation, e).exit(),
+        },
+        (Some(_), _) => Cli::command()
+            .error(ErrorKind::ArgumentConflict, "--nonce-string 只能和 --nonce-alphabet 一起使用")
+            .exit(),
+        (None, _) => args.nonce.expect("clap 保证 --nonce 和 --nonce-string 之一存在"),
+    };
+    if nonce > encoding.max_nonce() {
+        Cli::command()
+            .error(ErrorKind::ValueValidation, format!("nonce编码 {} 最大只能表示 {}", encoding, encoding.max_nonce()))
+            .exit();
+    }
+    let result = pow_rs::verify_with(&args.prefix, nonce, args.difficulty.resolve(), args.algo, encoding);
+
+    if args.nonce_string.is_some() {
+        println!("字符串对应的nonce序号: {}", nonce);
+    }
+    print_encoded_nonce(&result.nonce_encoding, nonce);
+    print_input(&result.input, &result.nonce_encoding);
+    println!("对应的{}哈希值: {}", result.algorithm, result.hash);
+    println!("前导零数量: {} 个十六进制前导零（{} 比特），要求 {}",
+             result.leading_hex_zeros(), result.leading_zero_bits, result.difficulty);
+
+    if result.is_valid() {
+        println!("验证通过");
+        ExitCode::SUCCESS
+    } else {
+        println!("验证失败: 工作量不足");
+        ExitCode::FAILURE
+    }
+}
+
+fn calculate(args: CalcArgs) -> ExitCode {
+    let difficulty = match args.hashes {
+        Some(hashes) => Difficulty::from_expected_hashes(hashes),
+        None => args.difficulty.resolve(),
+    };
+    let target = difficulty.to_target();
+    let expected = difficulty.expected_hashes();
+
+    match difficulty.hex_zeros() {
+        Some(zeros) => println!("十六进制前导零: {}", zeros),
+        None => println!("十六进制前导零: {:.2}（等效）", expected.log2() / 4.0),
+    }
+    match difficulty.bits() {
+        Some(bits) => println!("前导零比特: {}", bits),
+        None => println!("前导零比特: {:.2}（等效）", expected.log2()),
+    }
+    println!("目标值: {}", hex::encode(target.to_be_bytes()));
+    println!("nBits: {:08x}", target.to_compact());
+    println!("期望哈希次数: {:.6e}", expected);
+
+    let (rate, source) = match args.rate {
+        Some(rate) => (rate, "指定"),
+        None => (measure_hash_rate("weimeityy", args.algo, rayon::current_num_threads()), "本机测量"),
+    };
+    println!("\n哈希速率: {:.2} 哈希/秒（{}）", rate, source);
+    println!("期望耗时: {}", format_secs(expected / rate));
+    println!("中位耗时: {}", format_secs(difficulty.hashes_for_probability(0.5) / rate));
+    println!("99% 概率耗时: {}", format_secs(difficulty.hashes_for_probability(0.99) / rate));
+    ExitCode::SUCCESS
+}
+
+fn bench(args: BenchArgs) -> ExitCode {
+    let threads: Vec<usize> = if args.threads.is_empty() {
+        let cores = rayon::current_num_threads();
+        let mut threads: Vec<usize> = (0..).map(|i| 1 << i).take_while(|&n| n < cores).collect();
+        threads.push(cores);
+        threads
+    } else {
+        args.threads.iter().map(|threads| threads.get()).collect()
+    };
+
+    if args.format == OutputFormat::Text {
+        match args.hashes {
+            Some(hashes) => println!("每个配置运行 {} 次哈希", hashes),
+            None => println!("每个配置运行 {:.2?}", args.duration),
+        }
+        if let Some(backend) = args.algo.iter().find_map(Algorithm::backend) {
+            println!("SHA-256 实现: {}", backend);
+        }
+        println!("{:>10} {:>6} {:>10} {:>14} {:>8} {:>13}",
+                 "算法", "线程", "前缀长度", "哈希次数", "耗时", "哈希/秒");
+    }
+
+    let mut results = Vec::new();
+    for &algorithm in &args.algo {
+        for &prefix_len in &args.prefix_lengths {
+            let prefix: String = "weimeityy".chars().cycle().take(prefix_len).collect();
+
+            for &threads in &threads {
+                // anytime 模式的目标不可能满足，运行时间只取决于预算
+                let miner = Miner::new(prefix.clone()).anytime().algorithm(algorithm).threads(threads);
+                let miner = match args.hashes {
+                    Some(hashes) => miner.max_hashes(hashes.get()),
+                    None => miner.timeout(args.duration),
+                };
+                let report = miner.build().expect("默认nonce范围总是有效").run();
+
+                let result = json!({
+                    "algorithm": algorithm.name(),
+                    "backend": algorithm.backend().map(Backend::name),
+                    "threads": threads,
+                    "prefix_len": prefix_len,
+                    "hashes": report.total_hashes,
+                    "elapsed": report.elapsed.as_secs_f64(),
+                    "rate": report.hashes_per_second(),
+                });
+                match args.format {
+                    OutputFormat::Text => println!("{:>12} {:>8} {:>14} {:>18} {:>10.2?} {:>16.0}",
+                                                   algorithm, threads, prefix_len, report.total_hashes,
+                                                   report.elapsed, report.hashes_per_second()),
+                    OutputFormat::Jsonl => emit(result),
+                    OutputFormat::Json => results.push(result),
+                }
+            }
+        }
+    }
+
+    if args.format == OutputFormat::Json {
+        emit(json!({ "results": results }));
     }
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,1299 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, Duration};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::checkpoint::Checkpoint;
+use crate::difficulty::{leading_zero_bits, Difficulty, MAX_BITS};
+use crate::encoding::NonceEncoding;
+use crate::hash::{self, Algorithm, PowHash};
+use crate::schedule::{NonceDispenser, RangeSet, DEFAULT_BATCH_SIZE};
+use crate::search::Searcher;
+
+/// 挖矿任务构建器
+#[derive(Debug, Clone)]
+pub struct Miner {
+    prefix: String,
+    difficulty: Difficulty,
+    algorithm: Algorithm,
+    nonce_encoding: NonceEncoding,
+    threads: Option<usize>,
+    start: u64,
+    end: u64,
+    lowest_nonce: bool,
+    count: Option<u64>,
+    covered: RangeSet,
+    found: Vec<u64>,
+    checkpoint_path: Option<PathBuf>,
+    checkpoint_interval: Duration,
+    timeout: Option<Duration>,
+    max_hashes: Option<u64>,
+    anytime: bool,
+    ladder: bool,
+    milestones: Vec<(u32, u64)>,
+}
+
+/// 进度报告间隔
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 默认的检查点保存间隔
+pub const DEFAULT_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+impl Miner {
+    /// 以固定字符串前缀创建构建器，默认难度为 6 个十六进制前导零（24 比特），搜索整个 u64 范围
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Miner {
+            prefix: prefix.into(),
+            difficulty: Difficulty::from_hex_zeros(6),
+            algorithm: Algorithm::Sha256,
+            nonce_encoding: NonceEncoding::Decimal,
+            threads: None,
+            start: 0,
+            end: u64::MAX,
+            lowest_nonce: false,
+            count: Some(1),
+            covered: RangeSet::new(),
+            found: Vec::new(),
+            checkpoint_path: None,
+            checkpoint_interval: DEFAULT_CHECKPOINT_INTERVAL,
+            timeout: None,
+            max_hashes: None,
+            anytime: false,
+            ladder: false,
+            milestones: Vec::new(),
+        }
+    }
+
+    /// 按检查点中的参数继续搜索，跳过已搜索完的nonce
+    pub fn resume(checkpoint: Checkpoint) -> Self {
+        let miner = Miner::new(checkpoint.prefix)
+            .difficulty(checkpoint.difficulty)
+            .algorithm(checkpoint.algorithm)
+            .nonce_encoding(checkpoint.nonce_encoding)
+            .range(checkpoint.start, checkpoint.end)
+            .lowest_nonce(checkpoint.lowest_nonce)
+            .solutions(checkpoint.count)
+            .skip(checkpoint.covered)
+            .found(checkpoint.solutions);
+        match checkpoint.difficulty.bits() {
+            Some(max_bits) if checkpoint.ladder => miner.ladder(max_bits).milestones(checkpoint.milestones),
+            _ if checkpoint.anytime => miner.anytime(),
+            _ => miner,
+        }
+    }
+
+    /// 难度要求
+    pub fn difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty = difficulty;
+        self
+    }
+
+    /// 哈希算法，默认为 SHA-256
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// nonce 追加到前缀之后的编码方式，默认为不补零的十进制
+    ///
+    /// 固定位数的十进制只能表示有限的nonce，搜索范围的终点会截断到 [`NonceEncoding::max_nonce`]。
+    pub fn nonce_encoding(mut self, encoding: NonceEncoding) -> Self {
+        self.nonce_encoding = encoding;
+        self
+    }
+
+    /// 不设难度目标，直到时间或哈希次数用完、范围搜索完毕或被中断，
+    /// 结果为期间见过的最佳哈希 [`Report::best`]
+    ///
+    /// 相当于要求 256 个前导零比特，实际不可能满足。
+    pub fn anytime(mut self) -> Self {
+        self.anytime = true;
+        self.difficulty(Difficulty::from_bits(MAX_BITS))
+    }
+
+    /// 难度阶梯：寻找达到 `max_bits` 个前导零比特的最小nonce，
+    /// 并记录达到每个更低比特数的最小nonce，见 [`Report::ladder`]
+    ///
+    /// 按最小nonce模式搜索，结果与线程数量无关。
+    pub fn ladder(mut self, max_bits: u32) -> Self {
+        assert!((1..=MAX_BITS).contains(&max_bits), "难度阶梯的比特数必须在 1 到 {} 之间", MAX_BITS);
+        self.ladder = true;
+        self.difficulty(Difficulty::from_bits(max_bits)).lowest_nonce(true).count(1)
+    }
+
+    /// 难度阶梯上之前已经达到的比特数和对应的nonce（例如检查点中保存的）
+    pub fn milestones(mut self, milestones: Vec<(u32, u64)>) -> Self {
+        self.milestones = milestones;
+        self
+    }
+
+    /// 工作线程数量，默认使用 rayon 的全局线程数
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// 搜索的nonce范围（包含两端）
+    pub fn range(mut self, start: u64, end: u64) -> Self {
+        self.start = start;
+        self.end = end;
+        self
+    }
+
+    /// 保证返回范围内满足条件的最小nonce，结果与线程数量和机器无关
+    ///
+    /// 找到结果后仍会搜索完所有更小的nonce，因此可能比默认模式多花一些时间。
+    pub fn lowest_nonce(mut self, lowest_nonce: bool) -> Self {
+        self.lowest_nonce = lowest_nonce;
+        self
+    }
+
+    /// 找到 `count` 个结果后停止，默认为 1；`count` 至少为 1
+    pub fn count(self, count: u64) -> Self {
+        self.solutions(Some(count))
+    }
+
+    /// 找出范围内的全部结果
+    pub fn all_solutions(self) -> Self {
+        self.solutions(None)
+    }
+
+    /// 需要的结果数量，`None` 表示找出范围内的全部结果
+    pub fn solutions(mut self, count: Option<u64>) -> Self {
+        assert!(count != Some(0), "结果数量至少为 1");
+        self.count = count;
+        self
+    }
+
+    /// 跳过已经搜索过的nonce
+    pub fn skip(mut self, covered: RangeSet) -> Self {
+        self.covered = covered;
+        self
+    }
+
+    /// 之前已经找到的结果（例如检查点中保存的），计入结果数量并出现在 [`Report::solutions`] 中
+    ///
+    /// 这些nonce所在的区间通常已经通过 [`skip`](Self::skip) 跳过，不会被重新找到。
+    pub fn found(mut self, mut nonces: Vec<u64>) -> Self {
+        nonces.sort_unstable();
+        nonces.dedup();
+        self.found = nonces;
+        self
+    }
+
+    /// 运行期间定期把进度保存到检查点文件
+    pub fn checkpoint(mut self, path: impl Into<PathBuf>) -> Self {
+        self.checkpoint_path = Some(path.into());
+        self
+    }
+
+    /// 检查点保存间隔，默认 30 秒
+    pub fn checkpoint_interval(mut self, interval: Duration) -> Self {
+        self.checkpoint_interval = interval;
+        self
+    }
+
+    /// 运行时间上限，到期后停止并返回 [`Outcome::Timeout`]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// 哈希次数上限，用完后停止并返回 [`Outcome::HashLimit`]
+    pub fn max_hashes(mut self, max_hashes: u64) -> Self {
+        self.max_hashes = Some(max_hashes);
+        self
+    }
+
+    /// nonce范围无效时返回错误；终点超出编码可表示的范围时截断到最大值
+    pub fn build(self) -> Result<MiningJob, BuildError> {
+        if self.start > self.end {
+            return Err(BuildError::StartAfterEnd { start: self.start, end: self.end });
+        }
+        let max_nonce = self.nonce_encoding.max_nonce();
+        if self.start > max_nonce {
+            return Err(BuildError::StartBeyondEncoding { start: self.start, max_nonce });
+        }
+        let end = self.end.min(max_nonce);
+        let threads = self
+            .threads
+            .unwrap_or_else(rayon::current_num_threads)
+            .max(1);
+
+        Ok(MiningJob {
+            prefix: self.prefix,
+            difficulty: self.difficulty,
+            algorithm: self.algorithm,
+            nonce_encoding: self.nonce_encoding,
+            threads,
+            start: self.start,
+            end,
+            lowest_nonce: self.lowest_nonce,
+            count: self.count,
+            covered: self.covered,
+            found: self.found,
+            checkpoint_path: self.checkpoint_path,
+            checkpoint_interval: self.checkpoint_interval,
+            timeout: self.timeout,
+            max_hashes: self.max_hashes,
+            anytime: self.anytime,
+            ladder: self.ladder,
+            milestones: self.milestones,
+            stop: Arc::new(AtomicBool::new(false)),
+        })
+    }
+}
+
+/// 构建挖矿任务失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// nonce范围起点大于终点
+    StartAfterEnd { start: u64, end: u64 },
+    /// nonce范围起点超出编码可表示的最大值
+    StartBeyondEncoding { start: u64, max_nonce: u64 },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::StartAfterEnd { start, end } => write!(f, "nonce范围起点 {} 大于终点 {}", start, end),
+            BuildError::StartBeyondEncoding { start, max_nonce } => {
+                write!(f, "nonce范围起点 {} 超出编码可表示的最大值 {}", start, max_nonce)
+            }
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+/// 已配置好的挖矿任务
+#[derive(Debug, Clone)]
+pub struct MiningJob {
+    prefix: String,
+    difficulty: Difficulty,
+    algorithm: Algorithm,
+    nonce_encoding: NonceEncoding,
+    threads: usize,
+    start: u64,
+    end: u64,
+    lowest_nonce: bool,
+    count: Option<u64>,
+    covered: RangeSet,
+    found: Vec<u64>,
+    checkpoint_path: Option<PathBuf>,
+    checkpoint_interval: Duration,
+    timeout: Option<Duration>,
+    max_hashes: Option<u64>,
+    anytime: bool,
+    ladder: bool,
+    milestones: Vec<(u32, u64)>,
+    stop: Arc<AtomicBool>,
+}
+
+/// 用于从其他线程（例如信号处理函数）请求停止任务
+#[derive(Debug, Clone)]
+pub struct StopHandle(Arc<AtomicBool>);
+
+impl StopHandle {
+    /// 请求停止，工作线程会在下一次汇总计数时退出
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// 任务结束的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// 找到了要求数量的结果；找出全部结果的模式下表示搜索完整个范围且至少有一个结果
+    Found,
+    /// 通过 [`StopHandle`] 中断
+    Interrupted,
+    /// 整个nonce范围都已搜索完毕，结果数量不足
+    NotFound,
+    /// 达到运行时间上限
+    Timeout,
+    /// 达到哈希次数上限
+    HashLimit,
+}
+
+/// 任务结束时的结果和统计
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub outcome: Outcome,
+    /// 找到的结果；最小nonce模式下按nonce升序，否则按找到的先后顺序
+    pub solutions: Vec<Solution>,
+    /// 总计尝试的哈希次数
+    pub total_hashes: u64,
+    pub elapsed: Duration,
+    /// 已搜索区间（包括检查点中的）中最大的nonce
+    pub highest_nonce: Option<u64>,
+    /// 本次运行见过的最佳（数值最小的）哈希
+    pub best: Option<BestHash>,
+    /// 难度阶梯模式下达到每个前导零比特数的最小nonce，按比特数升序；
+    /// 只包含更小的nonce都已搜索过、不会再变的级别
+    pub ladder: Vec<Milestone>,
+}
+
+/// 难度阶梯上达到某个前导零比特数的最小nonce
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Milestone {
+    /// 达到的前导零比特数
+    pub bits: u32,
+    pub nonce: u64,
+    /// 十六进制哈希值
+    pub hash: String,
+    /// 从范围起点依次搜索到该nonce所需的哈希次数，与线程数量无关
+    pub hashes_tried: u64,
+    pub elapsed: Duration,
+}
+
+/// 目前见过的最佳哈希，用于衡量未找到结果时离目标还有多远
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestHash {
+    pub nonce: u64,
+    /// 十六进制哈希值
+    pub hash: String,
+    /// 前导零比特数
+    pub leading_zero_bits: u32,
+}
+
+impl BestHash {
+    fn new(nonce: u64, digest: &[u8; 32]) -> Self {
+        BestHash {
+            nonce,
+            hash: hex::encode(digest),
+            leading_zero_bits: leading_zero_bits(digest),
+        }
+    }
+}
+
+impl Report {
+    /// 第一个结果
+    pub fn solution(&self) -> Option<&Solution> {
+        self.solutions.first()
+    }
+
+    /// 平均哈希速率
+    pub fn hashes_per_second(&self) -> f64 {
+        self.total_hashes as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+/// 满足难度要求的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub nonce: u64,
+    /// 十六进制哈希值
+    pub hash: String,
+    /// 找到结果时总计尝试的哈希次数（多线程时为近似值）
+    pub hashes_tried: u64,
+    pub elapsed: Duration,
+}
+
+/// 挖矿过程中的周期性进度
+#[derive(Debug, Clone)]
+pub struct Progress {
+    /// 最近一个统计周期内的哈希速率
+    pub hashes_per_second: f64,
+    /// 总计尝试的哈希次数
+    pub total_hashes: u64,
+    /// 自任务开始经过的时间
+    pub elapsed: Duration,
+    /// 目前所有线程见过的最佳哈希
+    pub best: Option<BestHash>,
+    /// 目前找到的结果数量，包括之前找到的
+    pub solutions: u64,
+}
+
+/// 挖矿过程中产生的事件
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Event {
+    Progress(Progress),
+    /// 找到一个满足条件的nonce；最小nonce模式下它不一定出现在最终结果中
+    Solution(Solution),
+    /// 难度阶梯模式下确定了达到某个前导零比特数的最小nonce
+    Milestone(Milestone),
+    /// 检查点已保存
+    CheckpointSaved,
+    /// 检查点保存失败，附带错误信息
+    CheckpointFailed(String),
+}
+
+impl MiningJob {
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    pub fn nonce_encoding(&self) -> &NonceEncoding {
+        &self.nonce_encoding
+    }
+
+    /// `nonce` 对应的完整哈希输入
+    pub fn input(&self, nonce: u64) -> Vec<u8> {
+        let mut input = self.prefix.as_bytes().to_vec();
+        self.nonce_encoding.encode_into(nonce, &mut input);
+        input
+    }
+
+    /// 是否为只求最佳哈希的 anytime 模式，见 [`Miner::anytime`]
+    pub fn is_anytime(&self) -> bool {
+        self.anytime
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// 搜索的nonce范围（包含两端）
+    pub fn range(&self) -> (u64, u64) {
+        (self.start, self.end)
+    }
+
+    pub fn lowest_nonce(&self) -> bool {
+        self.lowest_nonce
+    }
+
+    /// 找到要求数量的结果所需的期望哈希次数；找出全部结果和 anytime 模式下没有意义
+    pub fn expected_hashes(&self) -> Option<f64> {
+        let count = self.remaining_count()?;
+        Some(count as f64 * self.difficulty.expected_hashes())
+    }
+
+    /// 扣除之前已找到的结果后还需要的数量
+    fn remaining_count(&self) -> Option<u64> {
+        let count = self.count.filter(|_| !self.is_anytime())?;
+        Some(count.saturating_sub(self.found.len() as u64))
+    }
+
+    /// 尝试 `hashes` 次后已找到要求数量结果的概率（泊松近似）
+    ///
+    /// 报告线程每秒调用一次，计算量与结果数量无关。
+    pub fn success_probability(&self, hashes: u64) -> Option<f64> {
+        let count = self.remaining_count()?;
+        Some(self.difficulty.count_probability(count, hashes))
+    }
+
+    /// 已找到 `solutions` 个结果（包括之前找到的）时，找齐要求数量还需要的期望哈希次数
+    ///
+    /// 每次尝试相互独立，已经花掉的哈希次数不影响之后的期望。
+    pub fn remaining_hashes(&self, solutions: u64) -> Option<f64> {
+        let count = self.count.filter(|_| !self.is_anytime())?;
+        Some(count.saturating_sub(solutions) as f64 * self.difficulty.expected_hashes())
+    }
+
+    /// 是否为难度阶梯模式，见 [`Miner::ladder`]
+    pub fn is_ladder(&self) -> bool {
+        self.ladder
+    }
+
+    /// 需要的结果数量，`None` 表示找出范围内的全部结果
+    pub fn count(&self) -> Option<u64> {
+        self.count
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn max_hashes(&self) -> Option<u64> {
+        self.max_hashes
+    }
+
+    /// 用于中断任务的句柄，中断后 [`run`](Self::run) 返回 [`Outcome::Interrupted`]
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle(self.stop.clone())
+    }
+
+    /// 启动前已经搜索过的nonce
+    pub fn covered(&self) -> &RangeSet {
+        &self.covered
+    }
+
+    /// 启动前已经找到的结果
+    pub fn found(&self) -> &[u64] {
+        &self.found
+    }
+
+    /// 以当前参数、已搜索区间和其中找到的结果、难度阶梯上达到的nonce生成检查点
+    pub fn to_checkpoint(
+        &self,
+        covered: RangeSet,
+        solutions: Vec<u64>,
+        milestones: Vec<(u32, u64)>,
+    ) -> Checkpoint {
+        Checkpoint {
+            prefix: self.prefix.clone(),
+            difficulty: self.difficulty,
+            algorithm: self.algorithm,
+            nonce_encoding: self.nonce_encoding.clone(),
+            start: self.start,
+            end: self.end,
+            lowest_nonce: self.lowest_nonce,
+            count: self.count,
+            anytime: self.anytime,
+            ladder: self.ladder,
+            covered,
+            solutions,
+            milestones,
+        }
+    }
+
+    fn save_checkpoint(&self, shared: &Shared) -> Option<Event> {
+        let path = self.checkpoint_path.as_ref()?;
+        // 工作线程先记录结果再标记区间已搜索，按相反的顺序读取，保存的区间中的结果不会遗漏
+        let covered = shared.covered.lock().unwrap().clone();
+        let mut solutions = shared.hits.lock().unwrap().clone();
+        solutions.sort_unstable();
+        let milestones = shared.milestones();
+
+        Some(match self.to_checkpoint(covered, solutions, milestones).save(path) {
+            Ok(()) => Event::CheckpointSaved,
+            Err(e) => Event::CheckpointFailed(format!("{}: {}", path.display(), e)),
+        })
+    }
+
+    /// 难度阶梯上在 `ladder` 之后新确定的级别：达到该级别的最小nonce之前的nonce都已搜索过
+    fn settled_milestones(&self, shared: &Shared, ladder: &[Milestone], elapsed: Duration) -> Vec<Milestone> {
+        let Some(levels) = &shared.ladder else {
+            return Vec::new();
+        };
+        let levels = levels.lock().unwrap().clone();
+        let covered = shared.covered.lock().unwrap();
+
+        levels[ladder.len()..]
+            .iter()
+            .map_while(|level| *level)
+            .take_while(|&(nonce, _)| covered.complement_within(self.start, nonce).is_empty())
+            .zip(ladder.len() as u32 + 1..)
+            .map(|((nonce, digest), bits)| Milestone {
+                bits,
+                nonce,
+                hash: hex::encode(digest),
+                hashes_tried: nonce - self.start + 1,
+                elapsed,
+            })
+            .collect()
+    }
+
+    /// 运行任务直到找到结果、被中断或搜索完整个范围
+    pub fn run(&self) -> Report {
+        self.run_with_events(|_| {})
+    }
+
+    /// 运行任务，每秒通过回调报告一次进度，找到结果时立即报告
+    pub fn run_with_events<F>(&self, mut on_event: F) -> Report
+    where
+        F: FnMut(Event) + Send,
+    {
+        let start_time = Instant::now();
+
+        // 用于发送找到的nonce
+        let (sender, receiver) = mpsc::channel::<Hit>();
+        let count = self.count.unwrap_or(u64::MAX);
+
+        // 范围较小时缩小批次，让每个线程都能分到工作
+        let pending = self.covered.complement_within(self.start, self.end);
+        let batch_size = (pending.count() / (self.threads as u128 * 4))
+            .clamp(1, DEFAULT_BATCH_SIZE as u128) as u64;
+
+        let shared = Shared {
+            prefix: &self.prefix,
+            difficulty: self.difficulty,
+            nonce_encoding: &self.nonce_encoding,
+            // 线程按需从共享游标领取尚未搜索的nonce批次
+            dispenser: NonceDispenser::from_ranges(&pending, batch_size),
+            count,
+            found_count: AtomicU64::new(0),
+            found: AtomicBool::new(false),
+            stop: &self.stop,
+            hash_count: AtomicU64::new(0),
+            lowest: self.lowest_nonce.then(LowestHits::default),
+            covered: Mutex::new(self.covered.clone()),
+            hashes_left: self.max_hashes.map(AtomicU64::new),
+            hash_limit_reached: AtomicBool::new(false),
+            timed_out: AtomicBool::new(false),
+            best: Mutex::new(None),
+            ladder: self
+                .difficulty
+                .bits()
+                .filter(|_| self.ladder)
+                .map(|max_bits| Mutex::new(vec![None; max_bits as usize])),
+            hits: Mutex::new(Vec::new()),
+        };
+
+        // 之前找到的结果直接计入，损坏或不属于本任务的nonce忽略
+        let previous: Vec<Solution> = self
+            .found
+            .iter()
+            .filter(|&&nonce| (self.start..=self.end).contains(&nonce))
+            .filter_map(|&nonce| {
+                let digest = self.algorithm.digest(&self.input(nonce));
+                self.difficulty.is_met_by(&digest).then(|| Solution {
+                    nonce,
+                    hash: hex::encode(digest),
+                    hashes_tried: 0,
+                    elapsed: Duration::ZERO,
+                })
+            })
+            .collect();
+        for solution in &previous {
+            shared.record_hit(solution.nonce);
+        }
+        for &(_, nonce) in &self.milestones {
+            if (self.start..=self.end).contains(&nonce) {
+                shared.reach(nonce, &self.algorithm.digest(&self.input(nonce)));
+            }
+        }
+        let deadline = self.timeout.map(|timeout| start_time + timeout);
+
+        let num_threads = self.threads;
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .expect("无法创建线程池");
+
+        thread::scope(|scope| {
+            let shared = &shared;
+
+            // 报告线程接收工作线程找到的结果，并定期报告进度和保存检查点；
+            // 所有工作线程退出后通道断开，报告线程随之结束
+            let reporter = scope.spawn(move || {
+                let mut solutions = previous;
+                let mut ladder: Vec<Milestone> = Vec::new();
+                let mut last_count = 0u64;
+                let mut last_time = Instant::now();
+                let mut last_checkpoint = Instant::now();
+                let mut next_report = last_time + REPORT_INTERVAL;
+
+                loop {
+                    // 到达时间上限时通知工作线程停止，之后继续等待通道断开
+                    let wake = match deadline {
+                        Some(deadline) if !shared.timed_out.load(Ordering::Relaxed) => {
+                            next_report.min(deadline)
+                        }
+                        _ => next_report,
+                    };
+                    let timeout = wake.saturating_duration_since(Instant::now());
+                    let disconnected = match receiver.recv_timeout(timeout) {
+                        Ok((nonce, digest, hashes_tried)) => {
+                            // 多个线程可能同时找到结果，超出要求数量的直接丢弃；
+                            // 最小nonce模式下更晚找到的结果可能更小，全部保留
+                            let wanted = self.lowest_nonce || (solutions.len() as u64) < count;
+                            if wanted && self.difficulty.is_met_by(&digest) {
+                                let solution = Solution {
+                                    nonce,
+                                    hash: hex::encode(digest),
+                                    hashes_tried,
+                                    elapsed: start_time.elapsed(),
+                                };
+                                on_event(Event::Solution(solution.clone()));
+                                solutions.push(solution);
+                            }
+                            false
+                        }
+                        Err(mpsc::RecvTimeoutError::Timeout) => false,
+                        Err(mpsc::RecvTimeoutError::Disconnected) => true,
+                    };
+
+                    // 难度阶梯上的级别要等更小的nonce都搜索过才能确定，按比特数依次报告
+                    for milestone in self.settled_milestones(shared, &ladder, start_time.elapsed()) {
+                        on_event(Event::Milestone(milestone.clone()));
+                        ladder.push(milestone);
+                    }
+                    if disconnected {
+                        break;
+                    }
+
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        shared.timed_out.store(true, Ordering::Relaxed);
+                    }
+                    if Instant::now() < next_report {
+                        continue;
+                    }
+                    next_report += REPORT_INTERVAL;
+
+                    let current_count = shared.hash_count.load(Ordering::Relaxed);
+                    let current_time = Instant::now();
+                    let elapsed = current_time.duration_since(last_time).as_secs_f64();
+                    let hashes_per_second = (current_count - last_count) as f64 / elapsed;
+
+                    on_event(Event::Progress(Progress {
+                        hashes_per_second,
+                        total_hashes: current_count,
+                        elapsed: start_time.elapsed(),
+                        best: shared.best_hash(),
+                        solutions: solutions.len() as u64,
+                    }));
+
+                    last_count = current_count;
+                    last_time = current_time;
+
+                    if last_checkpoint.elapsed() >= self.checkpoint_interval {
+                        last_checkpoint = Instant::now();
+                        if let Some(event) = self.save_checkpoint(shared) {
+                            on_event(event);
+                        }
+                    }
+                }
+                (on_event, solutions, ladder)
+            });
+
+            // 创建线程池并行处理
+            pool.scope(|s| {
+                for _ in 0..num_threads {
+                    let sender = sender.clone();
+                    s.spawn(move |_| match self.algorithm {
+                        Algorithm::Sha256 => mine_range::<hash::Sha256>(shared, sender),
+                        Algorithm::Sha256d => mine_range::<hash::Sha256d>(shared, sender),
+                        Algorithm::Sha3_256 => mine_range::<hash::Sha3_256>(shared, sender),
+                        Algorithm::Keccak256 => mine_range::<hash::Keccak256>(shared, sender),
+                        Algorithm::Blake2b => mine_range::<hash::Blake2b>(shared, sender),
+                        Algorithm::Blake3 => mine_range::<hash::Blake3>(shared, sender),
+                    });
+                }
+            });
+
+            let total_hashes = shared.hash_count.load(Ordering::Relaxed);
+            let elapsed = start_time.elapsed();
+
+            // 所有工作线程都已退出，关闭通道让报告线程收完剩余结果后退出
+            drop(sender);
+            let (mut on_event, mut solutions, ladder) = reporter.join().expect("报告线程异常退出");
+            if let Some(event) = self.save_checkpoint(shared) {
+                on_event(event);
+            }
+
+            // 最小nonce模式下所有更小的nonce都已搜索完毕，取最小的若干个
+            if self.lowest_nonce {
+                solutions.sort_by_key(|solution| solution.nonce);
+            }
+            solutions.truncate(count.try_into().unwrap_or(usize::MAX));
+
+            // 最小nonce模式下只有返回的最大nonce之前全部搜索过，结果才确实是最小的；
+            // 被中断或超出预算而放弃的更小批次里可能还有结果
+            let covered = shared.covered.lock().unwrap().clone();
+            let complete = !self.lowest_nonce
+                || solutions
+                    .last()
+                    .is_none_or(|last| covered.complement_within(self.start, last.nonce).is_empty());
+
+            let outcome = if complete && solutions.len() as u64 >= count {
+                Outcome::Found
+            } else if self.stop.load(Ordering::Relaxed) {
+                Outcome::Interrupted
+            } else if shared.timed_out.load(Ordering::Relaxed) {
+                Outcome::Timeout
+            } else if shared.hash_limit_reached.load(Ordering::Relaxed) {
+                Outcome::HashLimit
+            } else if complete && self.count.is_none() && !solutions.is_empty() {
+                Outcome::Found
+            } else {
+                Outcome::NotFound
+            };
+
+            Report {
+                outcome,
+                solutions,
+                total_hashes,
+                elapsed,
+                highest_nonce: covered.ranges().last().map(|&(_, last)| last),
+                best: shared.best_hash(),
+                ladder,
+            }
+        })
+    }
+}
+
+/// 最小nonce模式下目前找到的最小的若干个nonce
+struct LowestHits {
+    /// 大顶堆，最多保留要求数量的nonce
+    heap: Mutex<BinaryHeap<u64>>,
+    /// 已凑齐要求数量时为其中最大的nonce，更大的nonce都可以跳过
+    bound: AtomicU64,
+}
+
+impl Default for LowestHits {
+    fn default() -> Self {
+        LowestHits {
+            heap: Mutex::new(BinaryHeap::new()),
+            bound: AtomicU64::new(u64::MAX),
+        }
+    }
+}
+
+/// 工作线程之间共享的状态
+struct Shared<'a> {
+    prefix: &'a str,
+    difficulty: Difficulty,
+    nonce_encoding: &'a NonceEncoding,
+    dispenser: NonceDispenser,
+    /// 需要的结果数量
+    count: u64,
+    /// 已找到的结果数量
+    found_count: AtomicU64,
+    /// 用于通知其他线程停止工作
+    found: AtomicBool,
+    /// 外部请求的停止
+    stop: &'a AtomicBool,
+    /// 用于统计已尝试的哈希次数
+    hash_count: AtomicU64,
+    /// 最小nonce模式下目前找到的最小nonce
+    lowest: Option<LowestHits>,
+    /// 已搜索完的nonce，用于保存检查点
+    covered: Mutex<RangeSet>,
+    /// 设置了哈希次数上限时剩余可分配的哈希次数
+    hashes_left: Option<AtomicU64>,
+    hash_limit_reached: AtomicBool,
+    timed_out: AtomicBool,
+    /// 所有工作线程见过的最佳nonce和摘要
+    best: Mutex<Option<(u64, [u8; 32])>>,
+    /// 难度阶梯模式下达到每个前导零比特数的最小nonce和摘要，下标为比特数减一
+    ladder: Option<Mutex<Vec<Level>>>,
+    /// 所有满足难度的nonce，包括之前找到的，用于保存检查点
+    hits: Mutex<Vec<u64>>,
+}
+
+impl Shared<'_> {
+    /// 是否还需要搜索从 `nonce` 开始的部分
+    fn should_search(&self, nonce: u64) -> bool {
+        // 哈希次数上限只限制新批次的分配，已申请到额度的批次照常搜索完
+        if self.stop.load(Ordering::Relaxed) || self.timed_out.load(Ordering::Relaxed) {
+            return false;
+        }
+        match &self.lowest {
+            // 最小nonce模式下只有更大的nonce可以跳过
+            Some(lowest) => nonce < lowest.bound.load(Ordering::Relaxed),
+            None => !self.found.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 从哈希次数预算中为 `[start, end]` 申请额度，返回实际可以搜索的终点
+    fn reserve(&self, start: u64, end: u64) -> Option<u64> {
+        let Some(hashes_left) = &self.hashes_left else {
+            return Some(end);
+        };
+
+        let wanted = end - start + 1;
+        let left = hashes_left
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| Some(left.saturating_sub(wanted)))
+            .unwrap();
+        // 额度不够整个批次时剩下的部分不会再被搜索
+        if left < wanted {
+            self.hash_limit_reached.store(true, Ordering::Relaxed);
+        }
+        if left == 0 {
+            return None;
+        }
+        Some(start + (left.min(wanted) - 1))
+    }
+
+    /// 与全局最佳摘要比较并保留更好的一个
+    fn merge_best(&self, candidate: Option<(u64, [u8; 32])>) {
+        let Some((nonce, digest)) = candidate else {
+            return;
+        };
+
+        let mut best = self.best.lock().unwrap();
+        if best
+            .as_ref()
+            .is_none_or(|(_, best)| self.difficulty.compare_digests(&digest, best).is_lt())
+        {
+            *best = Some((nonce, digest));
+        }
+    }
+
+    /// 从 `from` 开始搜索时应使用的难度：难度阶梯模式下为最低的一个在 `from` 之前尚未达到的比特数
+    fn search_difficulty(&self, from: u64) -> Difficulty {
+        let Some(levels) = &self.ladder else {
+            return self.difficulty;
+        };
+        // 比特数越高最小nonce越大，已在 from 之前达到的级别不会再变
+        let levels = levels.lock().unwrap();
+        let open = levels
+            .iter()
+            .position(|level| level.is_none_or(|(nonce, _)| nonce > from))
+            .unwrap_or(levels.len() - 1);
+        Difficulty::from_bits(open as u32 + 1)
+    }
+
+    /// 难度阶梯模式下用 `nonce` 更新它达到的各个比特数的最小nonce
+    fn reach(&self, nonce: u64, digest: &[u8; 32]) {
+        let Some(levels) = &self.ladder else {
+            return;
+        };
+        let mut levels = levels.lock().unwrap();
+        let bits = leading_zero_bits(digest).min(levels.len() as u32);
+        for level in &mut levels[..bits as usize] {
+            if level.is_none_or(|(lowest, _)| nonce < lowest) {
+                *level = Some((nonce, *digest));
+            }
+        }
+    }
+
+    /// 难度阶梯上目前达到的比特数和对应的最小nonce，用于保存检查点
+    fn milestones(&self) -> Vec<(u32, u64)> {
+        let Some(levels) = &self.ladder else {
+            return Vec::new();
+        };
+        (1..)
+            .zip(levels.lock().unwrap().iter())
+            .filter_map(|(bits, level)| level.map(|(nonce, _)| (bits, nonce)))
+            .collect()
+    }
+
+    fn best_hash(&self) -> Option<BestHash> {
+        self.best
+            .lock()
+            .unwrap()
+            .map(|(nonce, digest)| BestHash::new(nonce, &digest))
+    }
+
+    /// 记录一个结果，凑齐要求数量后通知其他线程
+    fn record_hit(&self, nonce: u64) {
+        self.hits.lock().unwrap().push(nonce);
+        match &self.lowest {
+            Some(lowest) => {
+                let mut heap = lowest.heap.lock().unwrap();
+                heap.push(nonce);
+                if heap.len() as u64 > self.count {
+                    heap.pop();
+                }
+                if heap.len() as u64 == self.count {
+                    lowest.bound.store(*heap.peek().unwrap(), Ordering::Relaxed);
+                }
+            }
+            None => {
+                if self.found_count.fetch_add(1, Ordering::Relaxed) + 1 >= self.count {
+                    self.found.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
+
+/// 工作线程发给报告线程的结果：nonce、摘要和当时总计尝试的哈希次数
+type Hit = (u64, [u8; 32], u64);
+
+/// 难度阶梯上一个级别目前的最小nonce和摘要，尚未达到时为 `None`
+type Level = Option<(u64, [u8; 32])>;
+
+fn mine_range<H: PowHash>(shared: &Shared, sender: mpsc::Sender<Hit>) {
+    let mut searcher =
+        Searcher::<H>::with_encoding(shared.prefix.as_bytes(), shared.difficulty, shared.nonce_encoding.clone());
+
+    while let Some((start, end)) = shared.dispenser.claim() {
+        // 批次按顺序分发，之后领取的批次只会更大
+        if !shared.should_search(start) {
+            break;
+        }
+        let Some(end) = shared.reserve(start, end) else {
+            break;
+        };
+
+        // 找到结果后从下一个nonce继续搜索本批次
+        let mut searched = 0u64;
+        let mut from = start;
+        loop {
+            searcher.set_difficulty(shared.search_difficulty(from));
+
+            // 周期性更新计数和检查是否已经找到足够的结果
+            let result = searcher.search(from, end, |hashes| {
+                searched += hashes;
+                shared.hash_count.fetch_add(hashes, Ordering::Relaxed);
+                shared.should_search(from)
+            });
+
+            let Some((nonce, digest)) = result else {
+                break;
+            };
+            // 找到符合条件的nonce；难度阶梯模式下也可能只是达到了更低的一级。
+            // 先记录再标记区间已搜索，报告线程和检查点看到的区间中的结果不会遗漏
+            let _ = sender.send((nonce, digest, shared.hash_count.load(Ordering::Relaxed)));
+            shared.reach(nonce, &digest);
+            if shared.difficulty.is_met_by(&digest) {
+                shared.record_hit(nonce);
+            }
+
+            match nonce.checked_add(1) {
+                Some(next) if next <= end && shared.should_search(next) => from = next,
+                _ => break,
+            }
+        }
+
+        // 记录本批次实际搜索过的nonce
+        if searched > 0 {
+            shared.covered.lock().unwrap().insert(start, start + (searched - 1));
+        }
+        shared.merge_best(searcher.take_best());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREFIX: &str = "weimeityy";
+
+    fn nonces(report: &Report) -> Vec<u64> {
+        report.solutions.iter().map(|solution| solution.nonce).collect()
+    }
+
+    #[test]
+    fn lowest_nonce_is_independent_of_threads() {
+        let lowest = |threads| {
+            Miner::new(PREFIX)
+                .difficulty(Difficulty::from_bits(12))
+                .range(0, 100_000)
+                .lowest_nonce(true)
+                .count(3)
+                .threads(threads)
+                .build().unwrap()
+                .run()
+        };
+
+        let single = lowest(1);
+        assert_eq!(single.outcome, Outcome::Found);
+        assert_eq!(single.solutions.len(), 3);
+        assert!(nonces(&single).is_sorted());
+        for threads in [2, 3, 8] {
+            let report = lowest(threads);
+            assert_eq!(report.outcome, Outcome::Found);
+            assert_eq!(nonces(&report), nonces(&single), "{} 个线程", threads);
+        }
+    }
+
+    #[test]
+    fn lowest_nonce_budget_below_first_hit_is_not_found() {
+        // 16 比特时最小的结果为 88493，预算用完之前不可能确定
+        for threads in [1, 4] {
+            let report = Miner::new(PREFIX)
+                .difficulty(Difficulty::from_bits(16))
+                .lowest_nonce(true)
+                .max_hashes(50_000)
+                .threads(threads)
+                .build().unwrap()
+                .run();
+            assert_eq!(report.outcome, Outcome::HashLimit);
+            assert!(report.solutions.is_empty());
+            assert_eq!(report.total_hashes, 50_000);
+        }
+    }
+
+    #[test]
+    fn lowest_nonce_with_unsearched_gap_is_not_found() {
+        // 已有的结果之前还有没搜索过的nonce，预算用完时不能当作最小的结果
+        let report = Miner::new(PREFIX)
+            .difficulty(Difficulty::from_bits(16))
+            .lowest_nonce(true)
+            .skip(RangeSet::from_range(100_000, 199_999))
+            .found(vec![150_261])
+            .max_hashes(1_000)
+            .threads(2)
+            .build().unwrap()
+            .run();
+        assert_eq!(report.outcome, Outcome::HashLimit);
+        assert_eq!(nonces(&report), [150_261]);
+    }
+
+    /// 12 比特时 [0, 20000] 中全部的结果
+    const HITS_12_BITS: [u64; 3] = [792, 16_626, 19_917];
+
+    fn first_hits(count: Option<u64>, threads: usize) -> Report {
+        Miner::new(PREFIX)
+            .difficulty(Difficulty::from_bits(12))
+            .range(0, 20_000)
+            .solutions(count)
+            .threads(threads)
+            .build().unwrap()
+            .run()
+    }
+
+    #[test]
+    fn count_returns_exactly_that_many() {
+        for (count, threads) in [(1, 1), (2, 1), (2, 4), (3, 4)] {
+            let report = first_hits(Some(count), threads);
+            assert_eq!(report.outcome, Outcome::Found);
+            assert_eq!(report.solutions.len() as u64, count);
+            for nonce in nonces(&report) {
+                assert!(HITS_12_BITS.contains(&nonce), "{}", nonce);
+            }
+        }
+
+        // 范围内的结果不够时搜索完整个范围
+        let report = first_hits(Some(4), 4);
+        assert_eq!(report.outcome, Outcome::NotFound);
+        assert_eq!(report.solutions.len(), 3);
+    }
+
+    #[test]
+    fn all_solutions_returns_every_hit() {
+        for threads in [1, 4] {
+            let report = first_hits(None, threads);
+            assert_eq!(report.outcome, Outcome::Found);
+            let mut found = nonces(&report);
+            found.sort_unstable();
+            assert_eq!(found, HITS_12_BITS);
+            assert_eq!(report.total_hashes, 20_001);
+        }
+    }
+
+    #[test]
+    fn previous_solutions_count_toward_count() {
+        let report = Miner::new(PREFIX)
+            .difficulty(Difficulty::from_bits(12))
+            .range(0, 20_000)
+            .count(3)
+            .skip(RangeSet::from_range(0, 16_626))
+            .found(vec![792, 16_626])
+            .threads(1)
+            .build().unwrap()
+            .run();
+        assert_eq!(report.outcome, Outcome::Found);
+        assert_eq!(nonces(&report), HITS_12_BITS);
+        assert_eq!(report.total_hashes, 19_917 - 16_626);
+    }
+
+    #[test]
+    #[should_panic(expected = "结果数量至少为 1")]
+    fn zero_solutions_is_rejected() {
+        let _ = Miner::new(PREFIX).lowest_nonce(true).solutions(Some(0));
+    }
+
+    #[test]
+    fn ladder_is_independent_of_threads() {
+        let ladder = |threads| {
+            let report = Miner::new(PREFIX).ladder(14).range(0, 100_000).threads(threads).build().unwrap().run();
+            assert_eq!(report.outcome, Outcome::Found);
+            report
+                .ladder
+                .iter()
+                .map(|milestone| (milestone.bits, milestone.nonce, milestone.hashes_tried, milestone.hash.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let single = ladder(1);
+        assert_eq!(single.iter().map(|milestone| milestone.0).collect::<Vec<_>>(), (1..=14).collect::<Vec<_>>());
+        for window in single.windows(2) {
+            assert!(window[0].1 <= window[1].1);
+        }
+        for (bits, nonce, hashes_tried, hash) in &single {
+            assert_eq!(*hashes_tried, nonce + 1);
+            let digest: [u8; 32] = hex::decode(hash).unwrap().try_into().unwrap();
+            assert!(leading_zero_bits(&digest) >= *bits);
+        }
+        for threads in [2, 8] {
+            assert_eq!(ladder(threads), single, "{} 个线程", threads);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "难度阶梯的比特数必须在 1 到 256 之间")]
+    fn ladder_without_levels_is_rejected() {
+        let _ = Miner::new(PREFIX).ladder(0);
+    }
+
+    #[test]
+    fn range_ending_at_u64_max_is_not_found() {
+        for threads in [1, 4] {
+            let report = Miner::new(PREFIX)
+                .difficulty(Difficulty::from_bits(64))
+                .range(u64::MAX - 10, u64::MAX)
+                .threads(threads)
+                .build().unwrap()
+                .run();
+            assert_eq!(report.outcome, Outcome::NotFound);
+            assert_eq!(report.total_hashes, 11);
+            assert_eq!(report.highest_nonce, Some(u64::MAX));
+            assert!(report.solutions.is_empty());
+        }
+    }
+
+    /// 不可能满足的难度，只会因为预算用完而停止
+    fn unreachable() -> Miner {
+        Miner::new(PREFIX).difficulty(Difficulty::from_bits(64))
+    }
+
+    #[test]
+    fn max_hashes_is_exact() {
+        for (max_hashes, threads) in [(1, 1), (12_345, 1), (12_345, 4), (250_001, 8)] {
+            let report = unreachable().max_hashes(max_hashes).threads(threads).build().unwrap().run();
+            assert_eq!(report.outcome, Outcome::HashLimit);
+            assert_eq!(report.total_hashes, max_hashes, "{} 个线程", threads);
+            let best = report.best.expect("应该记录见过的最佳哈希");
+            assert!(best.nonce < max_hashes);
+            assert!(report.solutions.is_empty());
+        }
+    }
+
+    #[test]
+    fn max_hashes_does_not_hide_a_found_result() {
+        let report = first_hits(Some(1), 1);
+        let hashes = report.solution().unwrap().nonce + 1;
+        let report = Miner::new(PREFIX)
+            .difficulty(Difficulty::from_bits(12))
+            .max_hashes(hashes)
+            .threads(1)
+            .build().unwrap()
+            .run();
+        assert_eq!(report.outcome, Outcome::Found);
+        assert_eq!(nonces(&report), [HITS_12_BITS[0]]);
+    }
+
+    #[test]
+    fn timeout_stops_the_search() {
+        let timeout = Duration::from_millis(200);
+        let report = unreachable().timeout(timeout).threads(2).build().unwrap().run();
+        assert_eq!(report.outcome, Outcome::Timeout);
+        assert!(report.elapsed >= timeout);
+        assert!(report.total_hashes > 0);
+        assert!(report.best.is_some());
+    }
+
+    #[test]
+    fn anytime_reports_best_hash() {
+        let report = Miner::new(PREFIX).anytime().range(0, 20_000).threads(4).build().unwrap().run();
+        assert_eq!(report.outcome, Outcome::NotFound);
+        assert_eq!(report.best.unwrap().nonce, 19_917);
+    }
+
+    #[test]
+    fn remaining_hashes_counts_solutions_found() {
+        let job = Miner::new(PREFIX).difficulty(Difficulty::from_bits(10)).count(3).build().unwrap();
+        assert_eq!(job.remaining_hashes(0), Some(3.0 * 1024.0));
+        assert_eq!(job.remaining_hashes(2), Some(1024.0));
+        assert_eq!(job.remaining_hashes(5), Some(0.0));
+
+        assert_eq!(Miner::new(PREFIX).all_solutions().build().unwrap().remaining_hashes(0), None);
+        assert_eq!(Miner::new(PREFIX).anytime().build().unwrap().remaining_hashes(0), None);
+    }
+
+    #[test]
+    fn invalid_range_is_rejected() {
+        let error = Miner::new(PREFIX).range(10, 9).build().unwrap_err();
+        assert_eq!(error, BuildError::StartAfterEnd { start: 10, end: 9 });
+
+        // 两位十进制补零最大只能表示 99，终点超出时截断，起点超出时报错
+        let padded = || Miner::new(PREFIX).nonce_encoding(NonceEncoding::DecimalPadded(2));
+        assert_eq!(padded().range(50, 1000).build().unwrap().range(), (50, 99));
+        let error = padded().range(100, 1000).build().unwrap_err();
+        assert_eq!(error, BuildError::StartBeyondEncoding { start: 100, max_nonce: 99 });
+    }
+}
@@ -0,0 +1,210 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 默认每批分发的nonce数量
+pub const DEFAULT_BATCH_SIZE: u64 = 100_000;
+
+/// 若干互不相交、按升序排列的nonce闭区间
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RangeSet {
+    ranges: Vec<(u64, u64)>,
+}
+
+impl RangeSet {
+    pub fn new() -> Self {
+        RangeSet::default()
+    }
+
+    /// 只包含 `[first, last]` 的集合
+    pub fn from_range(first: u64, last: u64) -> Self {
+        let mut set = RangeSet::new();
+        set.insert(first, last);
+        set
+    }
+
+    /// 加入 `[first, last]`，与已有的重叠或相邻区间合并
+    pub fn insert(&mut self, first: u64, last: u64) {
+        assert!(first <= last, "区间起点不能大于终点");
+
+        // 第一个可能与新区间重叠或相邻的区间
+        let lo = self.ranges.partition_point(|&(_, l)| l.saturating_add(1) < first);
+        // 第一个完全位于新区间之后且不相邻的区间
+        let hi = self.ranges.partition_point(|&(f, _)| f <= last.saturating_add(1));
+
+        let mut merged = (first, last);
+        if lo < hi {
+            merged.0 = merged.0.min(self.ranges[lo].0);
+            merged.1 = merged.1.max(self.ranges[hi - 1].1);
+        }
+        self.ranges.splice(lo..hi, [merged]);
+    }
+
+    /// 按升序排列的区间
+    pub fn ranges(&self) -> &[(u64, u64)] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// 集合中nonce的总数
+    pub fn count(&self) -> u128 {
+        self.ranges
+            .iter()
+            .map(|&(first, last)| (last - first) as u128 + 1)
+            .sum()
+    }
+
+    /// `[start, end]` 中不属于本集合的部分
+    pub fn complement_within(&self, start: u64, end: u64) -> RangeSet {
+        let mut rest = RangeSet::new();
+        let mut next = Some(start);
+
+        for &(first, last) in &self.ranges {
+            let Some(from) = next else { break };
+            if last < from {
+                continue;
+            }
+            if first > end {
+                break;
+            }
+            if first > from {
+                rest.ranges.push((from, first - 1));
+            }
+            next = last.checked_add(1);
+        }
+
+        if let Some(from) = next.filter(|&from| from <= end) {
+            rest.ranges.push((from, end));
+        }
+        rest
+    }
+}
+
+/// 按需向工作线程分发nonce批次的共享游标
+///
+/// 批次按顺序分发，因此已领取的部分始终是待搜索nonce空间的连续前缀，
+/// 与线程数量无关；先完成的线程会继续领取新批次而不是空闲等待。
+#[derive(Debug)]
+pub struct NonceDispenser {
+    pending: Vec<(u64, u64)>,
+    /// 截至每个区间（含）的累计批次数
+    batch_ends: Vec<u64>,
+    batch_size: u64,
+    /// 下一个待分发批次的序号
+    next_batch: AtomicU64,
+}
+
+impl NonceDispenser {
+    /// 分发 `[start, end]` 内的nonce，每批 `batch_size` 个
+    pub fn new(start: u64, end: u64, batch_size: u64) -> Self {
+        assert!(start <= end, "nonce范围起点不能大于终点");
+        Self::from_ranges(&RangeSet::from_range(start, end), batch_size)
+    }
+
+    /// 只分发 `pending` 中的nonce，批次不会跨越区间
+    pub fn from_ranges(pending: &RangeSet, batch_size: u64) -> Self {
+        let batch_size = batch_size.max(1);
+        let mut total = 0u64;
+        let batch_ends = pending
+            .ranges()
+            .iter()
+            .map(|&(first, last)| {
+                total = total.saturating_add((last - first) / batch_size + 1);
+                total
+            })
+            .collect();
+
+        NonceDispenser {
+            pending: pending.ranges().to_vec(),
+            batch_ends,
+            batch_size,
+            next_batch: AtomicU64::new(0),
+        }
+    }
+
+    /// 领取下一批nonce `[first, last]`，范围耗尽时返回 `None`
+    pub fn claim(&self) -> Option<(u64, u64)> {
+        let index = self.next_batch.fetch_add(1, Ordering::Relaxed);
+        let range = self.batch_ends.partition_point(|&batch_end| batch_end <= index);
+        let &(range_first, range_last) = self.pending.get(range)?;
+
+        let skipped = if range == 0 { 0 } else { self.batch_ends[range - 1] };
+        let first = range_first + (index - skipped) * self.batch_size;
+        let last = first.saturating_add(self.batch_size - 1).min(range_last);
+        Some((first, last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ranges: &[(u64, u64)]) -> RangeSet {
+        let mut set = RangeSet::new();
+        for &(first, last) in ranges {
+            set.insert(first, last);
+        }
+        set
+    }
+
+    #[test]
+    fn insert_merges_overlapping_and_adjacent() {
+        let mut ranges = set(&[(10, 19), (40, 49)]);
+        assert_eq!(ranges.ranges(), &[(10, 19), (40, 49)]);
+
+        // 相邻的区间合并
+        ranges.insert(20, 24);
+        assert_eq!(ranges.ranges(), &[(10, 24), (40, 49)]);
+        ranges.insert(5, 9);
+        assert_eq!(ranges.ranges(), &[(5, 24), (40, 49)]);
+
+        // 不相邻的区间单独保留并保持升序
+        ranges.insert(30, 31);
+        assert_eq!(ranges.ranges(), &[(5, 24), (30, 31), (40, 49)]);
+
+        // 跨越多个区间时全部合并
+        ranges.insert(20, 45);
+        assert_eq!(ranges.ranges(), &[(5, 49)]);
+        ranges.insert(6, 7);
+        assert_eq!(ranges.ranges(), &[(5, 49)]);
+        assert_eq!(ranges.count(), 45);
+    }
+
+    #[test]
+    fn insert_at_u64_bounds() {
+        let ranges = set(&[(u64::MAX - 1, u64::MAX), (0, 0), (1, u64::MAX - 2)]);
+        assert_eq!(ranges.ranges(), &[(0, u64::MAX)]);
+        assert_eq!(ranges.count(), u64::MAX as u128 + 1);
+    }
+
+    #[test]
+    fn complement_within_range() {
+        let ranges = set(&[(10, 19), (30, 39)]);
+        assert_eq!(ranges.complement_within(0, 50).ranges(), &[(0, 9), (20, 29), (40, 50)]);
+        assert_eq!(ranges.complement_within(15, 35).ranges(), &[(20, 29)]);
+        assert_eq!(ranges.complement_within(10, 19).ranges(), &[]);
+        assert_eq!(ranges.complement_within(40, 45).ranges(), &[(40, 45)]);
+        assert_eq!(RangeSet::new().complement_within(3, 7).ranges(), &[(3, 7)]);
+        assert!(set(&[(0, u64::MAX)]).complement_within(0, u64::MAX).is_empty());
+    }
+
+    #[test]
+    fn claim_walks_pending_ranges_in_order() {
+        let pending = set(&[(0, 9), (20, 24), (100, 100)]);
+        let dispenser = NonceDispenser::from_ranges(&pending, 4);
+
+        let batches: Vec<_> = std::iter::from_fn(|| dispenser.claim()).collect();
+        // 批次不跨越区间，区间末尾的批次可能不满
+        assert_eq!(batches, [(0, 3), (4, 7), (8, 9), (20, 23), (24, 24), (100, 100)]);
+        assert_eq!(dispenser.claim(), None);
+    }
+
+    #[test]
+    fn claim_until_u64_max() {
+        let dispenser = NonceDispenser::new(u64::MAX - 5, u64::MAX, 4);
+        assert_eq!(dispenser.claim(), Some((u64::MAX - 5, u64::MAX - 2)));
+        assert_eq!(dispenser.claim(), Some((u64::MAX - 1, u64::MAX)));
+        assert_eq!(dispenser.claim(), None);
+    }
+}
@@ -0,0 +1,369 @@
+use crate::difficulty::{ByteOrder, Difficulty};
+use crate::encoding::NonceEncoding;
+use crate::hash::PowHash;
+use crate::simd::MAX_LANES;
+
+/// 每搜索多少个nonce回调一次，用于汇总计数和检查停止标志
+pub const FLUSH_INTERVAL: u64 = 100_000;
+
+/// prefix + 编码后nonce 的输入缓冲区，十进制和字符集nonce在原地逐位递增
+#[derive(Debug, Clone)]
+pub struct NonceInput {
+    buf: Vec<u8>,
+    prefix_len: usize,
+    encoding: NonceEncoding,
+    nonce: u64,
+    /// 字符集nonce每个字符在字符集中的下标
+    digits: Vec<u8>,
+}
+
+impl NonceInput {
+    pub fn new(prefix: &[u8]) -> Self {
+        Self::with_encoding(prefix, NonceEncoding::Decimal)
+    }
+
+    /// 使用指定的nonce编码
+    pub fn with_encoding(prefix: &[u8], encoding: NonceEncoding) -> Self {
+        let mut buf = Vec::with_capacity(prefix.len() + encoding.max_len() + 1);
+        buf.extend_from_slice(prefix);
+        NonceInput {
+            buf,
+            prefix_len: prefix.len(),
+            encoding,
+            nonce: 0,
+            digits: Vec::new(),
+        }
+    }
+
+    /// 把nonce部分重写为 `nonce` 的编码
+    pub fn set(&mut self, nonce: u64) {
+        self.nonce = nonce;
+        self.buf.truncate(self.prefix_len);
+        match &self.encoding {
+            NonceEncoding::Alphabet(alphabet) => {
+                alphabet.digits_into(nonce, &mut self.digits);
+                let chars = alphabet.chars();
+                self.buf.extend(self.digits.iter().map(|&digit| chars[digit as usize]));
+            }
+            encoding => encoding.encode_into(nonce, &mut self.buf),
+        }
+    }
+
+    /// nonce 加一
+    pub fn increment(&mut self) {
+        self.advance(1);
+    }
+
+    /// nonce 加上 `n`（不超过 9），用于多通道时一次跳过一组nonce
+    ///
+    /// 十进制数字原地加上 `n`，进位到最高位时数字长度加一；补零的十进制在范围内不会进位到最高位之外。
+    pub fn advance(&mut self, n: u8) {
+        debug_assert!(n <= 9);
+        match self.encoding {
+            NonceEncoding::Decimal | NonceEncoding::DecimalPadded(_) => {}
+            NonceEncoding::Alphabet(_) => return self.advance_alphabet(n),
+            _ => return self.set(self.nonce.wrapping_add(n as u64)),
+        }
+        self.nonce = self.nonce.wrapping_add(n as u64);
+
+        let last = self.buf.len() - 1;
+        let digit = self.buf[last] + n;
+        if digit <= b'9' {
+            self.buf[last] = digit;
+            return;
+        }
+
+        self.buf[last] = digit - 10;
+        for digit in self.buf[self.prefix_len..last].iter_mut().rev() {
+            if *digit == b'9' {
+                *digit = b'0';
+            } else {
+                *digit += 1;
+                return;
+            }
+        }
+        // 进位到最高位，例如 95 + 8 -> 103
+        self.buf.insert(self.prefix_len, b'1');
+    }
+
+    /// 当前完整的哈希输入
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// 字符集nonce按字符集的进制原地加上 `n`，最高位也进位时长度改变，重新生成整个字符串
+    fn advance_alphabet(&mut self, n: u8) {
+        let NonceEncoding::Alphabet(alphabet) = &self.encoding else {
+            unreachable!("只用于字符集nonce");
+        };
+        let chars = alphabet.chars();
+        let base = chars.len() as u32;
+
+        let mut carry = n as u32;
+        for (digit, byte) in self.digits.iter_mut().zip(&mut self.buf[self.prefix_len..]).rev() {
+            let sum = *digit as u32 + carry;
+            *digit = (sum % base) as u8;
+            *byte = chars[*digit as usize];
+            carry = sum / base;
+            if carry == 0 {
+                break;
+            }
+        }
+
+        self.nonce = self.nonce.wrapping_add(n as u64);
+        if carry > 0 {
+            self.set(self.nonce);
+        }
+    }
+}
+
+/// 单线程的nonce搜索器，热循环中不分配内存
+///
+/// 前缀只在创建时由哈希算法吸收一次，`inputs` 中只保存前缀剩余字节和nonce。
+/// 哈希算法支持多通道时，连续的若干个nonce一起计算。
+#[derive(Debug, Clone)]
+pub struct Searcher<H: PowHash> {
+    hasher: H,
+    /// 每个通道一个输入，第 i 个通道保存当前nonce + i
+    inputs: Vec<NonceInput>,
+    difficulty: Difficulty,
+    /// 目前见过的最佳（数值最小的）摘要
+    best: Option<(u64, [u8; 32])>,
+    /// 最佳摘要最高的 8 个字节，用于快速排除更差的摘要
+    best_key: u64,
+}
+
+impl<H: PowHash> Searcher<H> {
+    pub fn new(prefix: &[u8], difficulty: Difficulty) -> Self {
+        Self::with_encoding(prefix, difficulty, NonceEncoding::Decimal)
+    }
+
+    /// 使用指定的nonce编码
+    pub fn with_encoding(prefix: &[u8], difficulty: Difficulty, encoding: NonceEncoding) -> Self {
+        let (hasher, tail) = H::absorb(prefix);
+        let lanes = hasher.lanes().clamp(1, MAX_LANES);
+        Searcher {
+            hasher,
+            inputs: vec![NonceInput::with_encoding(tail, encoding); lanes],
+            difficulty,
+            best: None,
+            best_key: u64::MAX,
+        }
+    }
+
+    /// 更换后续搜索使用的难度
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+    }
+
+    /// 取出自上次调用以来见过的最佳nonce和摘要
+    pub fn take_best(&mut self) -> Option<(u64, [u8; 32])> {
+        self.best_key = u64::MAX;
+        self.best.take()
+    }
+
+    /// 在 `[start, end]` 内顺序搜索第一个满足难度的nonce
+    ///
+    /// 大约每搜索 [`FLUSH_INTERVAL`] 个nonce以及结束时调用一次 `on_flush`，
+    /// 参数为自上次回调以来的哈希次数；返回 `false` 时提前停止搜索。
+    pub fn search<F>(&mut self, start: u64, end: u64, mut on_flush: F) -> Option<(u64, [u8; 32])>
+    where
+        F: FnMut(u64) -> bool,
+    {
+        let lanes = self.inputs.len();
+        let mut nonce = start;
+        let mut local_hash_count = 0u64;
+        let mut result = None;
+        let mut digests = [[0u8; 32]; MAX_LANES];
+
+        // 超出 u64 的通道永远凑不满一组，不会参与计算
+        for (lane, input) in self.inputs.iter_mut().enumerate() {
+            input.set(start.saturating_add(lane as u64));
+        }
+
+        loop {
+            // 剩余nonce足够且各通道数字位数相同时一起计算，否则只算第一个通道
+            let width = if lanes > 1 && end - nonce >= lanes as u64 - 1 && self.same_length() {
+                let mut tails = [&[][..]; MAX_LANES];
+                for (tail, input) in tails.iter_mut().zip(&self.inputs) {
+                    *tail = input.as_bytes();
+                }
+                self.hasher.finalize_lanes(&tails[..lanes], &mut digests[..lanes]);
+                lanes
+            } else {
+                digests[0] = self.hasher.finalize(self.inputs[0].as_bytes());
+                1
+            };
+
+            // 按nonce顺序检查，命中之后的结果不计入
+            for (lane, digest) in digests[..width].iter().enumerate() {
+                let candidate = nonce + lane as u64;
+                local_hash_count += 1;
+
+                let key = self.sort_key(digest);
+                if key <= self.best_key
+                    && self
+                        .best
+                        .as_ref()
+                        .is_none_or(|(_, best)| self.difficulty.compare_digests(digest, best).is_lt())
+                {
+                    self.best = Some((candidate, *digest));
+                    self.best_key = key;
+                }
+
+                // 直接在原始摘要上检查难度
+                if self.difficulty.is_met_by(digest) {
+                    result = Some((candidate, *digest));
+                    break;
+                }
+            }
+            if result.is_some() {
+                break;
+            }
+
+            // 范围终点可能是 u64::MAX，先判断再递增以免溢出
+            let last = nonce + (width as u64 - 1);
+            if last == end {
+                break;
+            }
+            nonce = last + 1;
+            for input in &mut self.inputs {
+                input.advance(width as u8);
+            }
+
+            if local_hash_count >= FLUSH_INTERVAL && !on_flush(std::mem::take(&mut local_hash_count)) {
+                break;
+            }
+        }
+
+        // 添加剩余的哈希计数
+        if local_hash_count > 0 {
+            on_flush(local_hash_count);
+        }
+        result
+    }
+
+    /// 按难度的字节序取摘要最高的 8 个字节
+    fn sort_key(&self, digest: &[u8; 32]) -> u64 {
+        match self.difficulty.byte_order() {
+            ByteOrder::BigEndian => u64::from_be_bytes(digest[..8].try_into().unwrap()),
+            ByteOrder::LittleEndian => u64::from_le_bytes(digest[24..].try_into().unwrap()),
+        }
+    }
+
+    /// 各通道的输入长度是否相同（跨越 9 -> 10 或 zz -> aaa 这样的长度变化时不同）
+    fn same_length(&self) -> bool {
+        let len = self.inputs[0].as_bytes().len();
+        self.inputs.iter().all(|input| input.as_bytes().len() == len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::Alphabet;
+    use crate::hash::Sha256;
+
+    const PREFIX: &[u8] = b"prefix";
+
+    /// 从每个起点原地前进 1 到 9 的结果都与直接设置为相加后的nonce一致
+    fn assert_advance_matches_set(encoding: NonceEncoding, starts: &[u64]) {
+        let mut input = NonceInput::with_encoding(PREFIX, encoding.clone());
+        let mut expected = NonceInput::with_encoding(PREFIX, encoding.clone());
+        for &start in starts {
+            for n in 1..=9 {
+                input.set(start);
+                input.advance(n);
+                expected.set(start + n as u64);
+                assert_eq!(input.as_bytes(), expected.as_bytes(), "{}: {} + {}", encoding, start, n);
+            }
+        }
+
+        // 连续前进时进位也要正确
+        let first = starts[0];
+        input.set(first);
+        for step in 1..=2000 {
+            input.increment();
+            expected.set(first + step);
+            assert_eq!(input.as_bytes(), expected.as_bytes(), "{}: {} + {}", encoding, first, step);
+        }
+    }
+
+    #[test]
+    fn advance_decimal() {
+        assert_advance_matches_set(NonceEncoding::Decimal, &[0, 5, 95, 99, 999, 9_999_995, 123_456_789]);
+
+        let mut input = NonceInput::new(PREFIX);
+        input.set(95);
+        input.advance(8);
+        assert_eq!(input.as_bytes(), b"prefix103");
+    }
+
+    #[test]
+    fn advance_decimal_padded() {
+        assert_advance_matches_set(NonceEncoding::DecimalPadded(4), &[0, 95, 995, 9989]);
+
+        // 超出补零位数后与不补零的十进制相同
+        let mut input = NonceInput::with_encoding(PREFIX, NonceEncoding::DecimalPadded(2));
+        input.set(95);
+        input.advance(8);
+        assert_eq!(input.as_bytes(), b"prefix103");
+        input.set(7);
+        input.advance(3);
+        assert_eq!(input.as_bytes(), b"prefix10");
+    }
+
+    #[test]
+    fn advance_alphabet() {
+        let alphabet = Alphabet::new(Alphabet::parse_set("a-z").unwrap(), 1, 3).unwrap();
+        // 25 为 z，701 为 zz，18277 为 zzz
+        assert_advance_matches_set(NonceEncoding::Alphabet(alphabet.clone()), &[0, 20, 25, 695, 701, 18_270]);
+
+        let mut input = NonceInput::with_encoding(PREFIX, NonceEncoding::Alphabet(alphabet));
+        input.set(701);
+        assert_eq!(input.as_bytes(), b"prefixzz");
+        input.increment();
+        assert_eq!(input.as_bytes(), b"prefixaaa");
+
+        // 二进制字符集每一位都会进位
+        let binary = Alphabet::new("01", 2, 64).unwrap();
+        assert_advance_matches_set(NonceEncoding::Alphabet(binary), &[0, 3, 11, 1 << 20, (1 << 30) - 7]);
+    }
+
+    #[test]
+    fn advance_binary_and_hex() {
+        for encoding in [NonceEncoding::Hex, NonceEncoding::Le64, NonceEncoding::Be64] {
+            assert_advance_matches_set(encoding, &[0, 0xf, 0xfa, 0xffff_fff9]);
+        }
+    }
+
+    /// 搜索 `[start, end]`，返回结果和回调汇总的哈希次数
+    fn search(difficulty: Difficulty, start: u64, end: u64) -> (Option<u64>, u64) {
+        let mut searcher = Searcher::<Sha256>::new(b"weimeityy", difficulty);
+        let mut hashes = 0;
+        let result = searcher.search(start, end, |count| {
+            hashes += count;
+            true
+        });
+        (result.map(|(nonce, _)| nonce), hashes)
+    }
+
+    #[test]
+    fn search_returns_first_hit_in_order() {
+        // 多通道时命中之后的通道不计入
+        assert_eq!(search(Difficulty::from_bits(16), 0, 200_000), (Some(88_493), 88_494));
+        assert_eq!(search(Difficulty::from_bits(16), 88_494, 200_000), (Some(112_664), 112_664 - 88_494 + 1));
+        assert_eq!(search(Difficulty::from_bits(16), 0, 88_492), (None, 88_493));
+    }
+
+    #[test]
+    fn search_until_u64_max() {
+        assert_eq!(search(Difficulty::from_bits(64), u64::MAX - 10, u64::MAX), (None, 11));
+        assert_eq!(search(Difficulty::from_bits(64), u64::MAX, u64::MAX), (None, 1));
+
+        let mut searcher = Searcher::<Sha256>::new(b"weimeityy", Difficulty::from_bits(64));
+        searcher.search(u64::MAX - 10, u64::MAX, |_| true);
+        let (nonce, _) = searcher.take_best().unwrap();
+        assert!(nonce >= u64::MAX - 10);
+    }
+}
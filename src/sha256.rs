@@ -0,0 +1,134 @@
+use sha2::compress256;
+use sha2::digest::generic_array::GenericArray;
+
+use crate::simd::{self, Backend, MAX_LANES};
+
+/// SHA-256 分组长度（字节）
+pub const BLOCK_LEN: usize = 64;
+
+/// SHA-256 初始哈希值
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+    0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// 压缩完前缀中所有完整 64 字节分组后的 SHA-256 中间状态
+///
+/// 每个nonce只需处理包含前缀剩余部分和nonce的最后一两个分组，
+/// 因此哈希速率不再随前缀长度下降。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Midstate {
+    state: [u32; 8],
+    /// 已压缩的字节数，总是 64 的整数倍
+    absorbed: u64,
+}
+
+impl Midstate {
+    /// 压缩 `prefix` 的所有完整分组，返回中间状态和未压缩的剩余字节
+    pub fn new(prefix: &[u8]) -> (Self, &[u8]) {
+        let full = prefix.len() / BLOCK_LEN * BLOCK_LEN;
+        let mut state = INITIAL_STATE;
+        compress_blocks(&mut state, &prefix[..full]);
+
+        let midstate = Midstate {
+            state,
+            absorbed: full as u64,
+        };
+        (midstate, &prefix[full..])
+    }
+
+    /// 从中间状态继续处理 `tail` 并完成填充，得到完整输入的摘要
+    pub fn finalize(&self, tail: &[u8]) -> [u8; 32] {
+        let mut state = self.state;
+        let full = tail.len() / BLOCK_LEN * BLOCK_LEN;
+        compress_blocks(&mut state, &tail[..full]);
+
+        // 填充: 0x80，若干 0，最后 8 字节为大端的消息比特长度
+        let rest = &tail[full..];
+        let mut block = [0u8; 2 * BLOCK_LEN];
+        block[..rest.len()].copy_from_slice(rest);
+        block[rest.len()] = 0x80;
+        let padded_len = if rest.len() < BLOCK_LEN - 8 { BLOCK_LEN } else { 2 * BLOCK_LEN };
+        let bit_len = (self.absorbed + tail.len() as u64) * 8;
+        block[padded_len - 8..padded_len].copy_from_slice(&bit_len.to_be_bytes());
+        compress_blocks(&mut state, &block[..padded_len]);
+
+        let mut digest = [0u8; 32];
+        for (chunk, word) in digest.chunks_exact_mut(4).zip(state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    /// 用多缓冲区实现同时计算多个等长 `tail` 的摘要
+    ///
+    /// `tails` 的数量必须等于 `backend.lanes()`；填充后超过两个分组时返回 `false`，
+    /// 调用方应改为逐个计算。
+    pub fn finalize_lanes(&self, backend: Backend, tails: &[&[u8]], digests: &mut [[u8; 32]]) -> bool {
+        assert_eq!(tails.len(), backend.lanes(), "输入数量必须等于通道数");
+
+        let mut blocks = [[0u8; 2 * BLOCK_LEN]; MAX_LANES];
+        let mut nblocks = 0;
+        for (tail, block) in tails.iter().zip(&mut blocks) {
+            match self.pad(tail, block) {
+                Some(n) if nblocks == 0 || n == nblocks => nblocks = n,
+                _ => return false,
+            }
+        }
+        simd::compress_lanes(backend, &self.state, &blocks, nblocks, digests);
+        true
+    }
+
+    /// 把 `tail` 连同填充写入 `block`，返回分组数；超过两个分组时返回 `None`
+    fn pad(&self, tail: &[u8], block: &mut [u8; 2 * BLOCK_LEN]) -> Option<usize> {
+        let padded_len = (tail.len() + 9).div_ceil(BLOCK_LEN) * BLOCK_LEN;
+        if padded_len > block.len() {
+            return None;
+        }
+
+        block[..tail.len()].copy_from_slice(tail);
+        block[tail.len()] = 0x80;
+        block[tail.len() + 1..padded_len - 8].fill(0);
+        let bit_len = (self.absorbed + tail.len() as u64) * 8;
+        block[padded_len - 8..padded_len].copy_from_slice(&bit_len.to_be_bytes());
+        Some(padded_len / BLOCK_LEN)
+    }
+}
+
+/// 压缩若干完整分组，`data` 长度必须是 64 的整数倍
+fn compress_blocks(state: &mut [u32; 8], data: &[u8]) {
+    for block in data.chunks_exact(BLOCK_LEN) {
+        compress256(state, std::slice::from_ref(GenericArray::from_slice(block)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+
+    #[test]
+    fn finalize_matches_sha2() {
+        // 前缀长度覆盖空前缀、填充恰好放得下和放不下长度字段、正好一个分组以及多个分组
+        for prefix_len in [0, 55, 56, 63, 64, 100, 128, 200] {
+            let prefix: Vec<u8> = (0..prefix_len).map(|i| (i * 13 + 5) as u8).collect();
+            let (midstate, rest) = Midstate::new(&prefix);
+            assert_eq!(rest.len(), prefix_len % BLOCK_LEN);
+
+            // 剩余字节加上nonce后可能落在一个、两个或更多分组中
+            for nonce_len in 0..=2 * BLOCK_LEN + 8 {
+                let nonce: Vec<u8> = (0..nonce_len).map(|i| (i * 7 + 1) as u8).collect();
+                let tail = [rest, &nonce].concat();
+                let input = [&prefix[..], &nonce].concat();
+                assert_eq!(
+                    midstate.finalize(&tail)[..],
+                    Sha256::digest(&input)[..],
+                    "前缀 {} 字节，nonce {} 字节",
+                    prefix_len,
+                    nonce_len
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,413 @@
+//! nonce 分组的 SHA-256 压缩：SHA 扩展指令（SHA-NI）交错计算 2 个nonce，
+//! 或者多缓冲区方式一条指令流同时处理 4 个（SSE4.1）或 8 个（AVX2）nonce
+//!
+//! 所有通道共享同一个前缀中间状态，只有最后一两个分组（前缀剩余字节、nonce 和填充）不同。
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use sha2::digest::generic_array::GenericArray;
+use sha2::{compress256, Digest, Sha256};
+
+use crate::sha256::{Midstate, BLOCK_LEN};
+
+/// 最多同时处理的通道数
+pub const MAX_LANES: usize = 8;
+
+/// SHA-256 轮常量
+#[cfg_attr(not(target_arch = "x86_64"), allow(dead_code))]
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// nonce 分组 SHA-256 的实现方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// 逐个计算
+    Scalar,
+    /// SHA 扩展指令，2 个通道交错执行
+    ShaNi,
+    /// 4 通道 SSE4.1
+    Sse41,
+    /// 8 通道 AVX2
+    Avx2,
+}
+
+impl Backend {
+    /// 本机可用且通过自检的最快实现，结果只计算一次
+    ///
+    /// 自检失败的实现会被跳过，最终总能退回到逐个计算。
+    pub fn detect() -> Backend {
+        static BACKEND: OnceLock<Backend> = OnceLock::new();
+        *BACKEND.get_or_init(|| {
+            [Backend::ShaNi, Backend::Avx2, Backend::Sse41]
+                .into_iter()
+                .find(|backend| backend.is_supported() && backend.self_check())
+                .unwrap_or(Backend::Scalar)
+        })
+    }
+
+    /// 当前CPU是否支持该实现
+    pub fn is_supported(self) -> bool {
+        match self {
+            Backend::Scalar => true,
+            #[cfg(target_arch = "x86_64")]
+            Backend::ShaNi => is_x86_feature_detected!("sha") && is_x86_feature_detected!("sse4.1"),
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse41 => is_x86_feature_detected!("sse4.1"),
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(not(target_arch = "x86_64"))]
+            _ => false,
+        }
+    }
+
+    /// 同时处理的通道数
+    pub fn lanes(self) -> usize {
+        match self {
+            Backend::Scalar => 1,
+            Backend::ShaNi => 2,
+            Backend::Sse41 => 4,
+            Backend::Avx2 => 8,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Scalar => "scalar",
+            Backend::ShaNi => "sha-ni",
+            Backend::Sse41 => "sse4.1",
+            Backend::Avx2 => "avx2",
+        }
+    }
+
+    /// 用不同的前缀长度和输入长度（一个和两个分组）与 sha2 的结果逐字节比较
+    fn self_check(self) -> bool {
+        let lanes = self.lanes();
+        for prefix_len in [0, 9, 64, 100] {
+            let prefix: Vec<u8> = (0..prefix_len).map(|i| (i * 7 + 3) as u8).collect();
+            let (midstate, rest) = Midstate::new(&prefix);
+
+            for tail_len in 0..=(2 * BLOCK_LEN - 9 - rest.len()) {
+                let tails: Vec<Vec<u8>> = (0..lanes)
+                    .map(|lane| {
+                        let mut tail = rest.to_vec();
+                        tail.extend((0..tail_len).map(|i| (i * 31 + lane * 101) as u8));
+                        tail
+                    })
+                    .collect();
+                let refs: Vec<&[u8]> = tails.iter().map(Vec::as_slice).collect();
+
+                let mut digests = [[0u8; 32]; MAX_LANES];
+                if !midstate.finalize_lanes(self, &refs, &mut digests) {
+                    return false;
+                }
+                for (tail, digest) in tails.iter().zip(&digests) {
+                    let input = [&prefix[..prefix.len() - rest.len()], tail].concat();
+                    if Sha256::digest(&input)[..] != digest[..] {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// 从同一个中间状态 `state` 出发，对每个通道压缩 `nblocks` 个已填充的分组
+///
+/// `blocks` 和 `digests` 至少要有 `backend.lanes()` 个元素。
+pub(crate) fn compress_lanes(
+    backend: Backend,
+    state: &[u32; 8],
+    blocks: &[[u8; 2 * BLOCK_LEN]],
+    nblocks: usize,
+    digests: &mut [[u8; 32]],
+) {
+    assert!(blocks.len() >= backend.lanes() && digests.len() >= backend.lanes());
+    assert!(nblocks <= 2);
+
+    // 调用方可以自行构造任意实现，每次都确认CPU支持（标准库会缓存检测结果）
+    match backend {
+        // SAFETY: 已确认CPU支持 SHA 扩展和 SSE4.1
+        #[cfg(target_arch = "x86_64")]
+        Backend::ShaNi if backend.is_supported() => unsafe { sha_ni::compress(state, blocks, nblocks, digests) },
+        // SAFETY: 已确认CPU支持 AVX2
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx2 if backend.is_supported() => unsafe { avx2::compress(state, blocks, nblocks, digests) },
+        // SAFETY: 已确认CPU支持 SSE4.1
+        #[cfg(target_arch = "x86_64")]
+        Backend::Sse41 if backend.is_supported() => unsafe { sse41::compress(state, blocks, nblocks, digests) },
+        _ => {
+            for (block, digest) in blocks.iter().zip(digests.iter_mut()).take(backend.lanes()) {
+                let mut lane_state = *state;
+                for chunk in block[..nblocks * BLOCK_LEN].chunks_exact(BLOCK_LEN) {
+                    compress256(&mut lane_state, std::slice::from_ref(GenericArray::from_slice(chunk)));
+                }
+                for (bytes, word) in digest.chunks_exact_mut(4).zip(lane_state) {
+                    bytes.copy_from_slice(&word.to_be_bytes());
+                }
+            }
+        }
+    }
+}
+
+/// SHA 扩展指令：`sha256rnds2` 一次完成两轮，消息扩展由 `sha256msg1`/`sha256msg2` 完成
+///
+/// 每个通道的轮函数是一条很长的依赖链，两个通道交错执行以掩盖指令延迟。
+#[cfg(target_arch = "x86_64")]
+mod sha_ni {
+    use std::arch::x86_64::*;
+
+    use super::{BLOCK_LEN, K};
+
+    const LANES: usize = 2;
+
+    /// 把每个 32 位字在大端字节和寄存器中的整数之间转换
+    #[target_feature(enable = "sse4.1")]
+    #[inline]
+    fn byte_swap_mask() -> __m128i {
+        _mm_set_epi64x(0x0c0d0e0f_08090a0b, 0x04050607_00010203)
+    }
+
+    #[target_feature(enable = "sha,sse4.1")]
+    pub(super) fn compress(state: &[u32; 8], blocks: &[[u8; 2 * BLOCK_LEN]], nblocks: usize, digests: &mut [[u8; 32]]) {
+        // 指令要求状态按 ABEF / CDGH 排列
+        // SAFETY: `state` 有 8 个字，storeu/loadu 不要求对齐
+        let (dcba, hgfe) = unsafe {
+            (
+                _mm_loadu_si128(state.as_ptr().cast()),
+                _mm_loadu_si128(state.as_ptr().add(4).cast()),
+            )
+        };
+        let cdab = _mm_shuffle_epi32::<0xb1>(dcba);
+        let efgh = _mm_shuffle_epi32::<0x1b>(hgfe);
+        let mut abef = [_mm_alignr_epi8::<8>(cdab, efgh); LANES];
+        let mut cdgh = [_mm_blend_epi16::<0xf0>(efgh, cdab); LANES];
+
+        let mask = byte_swap_mask();
+        for block in 0..nblocks {
+            let (saved_abef, saved_cdgh) = (abef, cdgh);
+
+            let mut w = [[_mm_setzero_si128(); 4]; LANES];
+            for (words, lane_blocks) in w.iter_mut().zip(blocks) {
+                let chunk = &lane_blocks[block * BLOCK_LEN..(block + 1) * BLOCK_LEN];
+                for (word, bytes) in words.iter_mut().zip(chunk.chunks_exact(16)) {
+                    // SAFETY: `bytes` 正好是一个向量的大小
+                    *word = _mm_shuffle_epi8(unsafe { _mm_loadu_si128(bytes.as_ptr().cast()) }, mask);
+                }
+            }
+
+            // 每次四轮，w[i % 4] 保存第 i 组的四个消息字
+            for i in 0..16 {
+                // SAFETY: K 有 64 个字
+                let k = unsafe { _mm_loadu_si128(K.as_ptr().add(4 * i).cast()) };
+                let mut msg = [k; LANES];
+                for lane in 0..LANES {
+                    msg[lane] = _mm_add_epi32(w[lane][i % 4], k);
+                    cdgh[lane] = _mm_sha256rnds2_epu32(cdgh[lane], abef[lane], msg[lane]);
+                }
+                if (3..15).contains(&i) {
+                    for words in &mut w {
+                        let carry = _mm_alignr_epi8::<4>(words[i % 4], words[(i + 3) % 4]);
+                        let next = _mm_add_epi32(words[(i + 1) % 4], carry);
+                        words[(i + 1) % 4] = _mm_sha256msg2_epu32(next, words[i % 4]);
+                    }
+                }
+                for lane in 0..LANES {
+                    let high = _mm_shuffle_epi32::<0x0e>(msg[lane]);
+                    abef[lane] = _mm_sha256rnds2_epu32(abef[lane], cdgh[lane], high);
+                }
+                if (1..13).contains(&i) {
+                    for words in &mut w {
+                        words[(i + 3) % 4] = _mm_sha256msg1_epu32(words[(i + 3) % 4], words[i % 4]);
+                    }
+                }
+            }
+
+            for lane in 0..LANES {
+                abef[lane] = _mm_add_epi32(abef[lane], saved_abef[lane]);
+                cdgh[lane] = _mm_add_epi32(cdgh[lane], saved_cdgh[lane]);
+            }
+        }
+
+        // 恢复 ABCD / EFGH 的顺序并转换为大端摘要
+        for ((digest, abef), cdgh) in digests.iter_mut().zip(abef).zip(cdgh) {
+            let feba = _mm_shuffle_epi32::<0x1b>(abef);
+            let dchg = _mm_shuffle_epi32::<0xb1>(cdgh);
+            let dcba = _mm_blend_epi16::<0xf0>(feba, dchg);
+            let hgfe = _mm_alignr_epi8::<8>(dchg, feba);
+            // SAFETY: `digest` 有 32 个字节，storeu 不要求对齐
+            unsafe {
+                _mm_storeu_si128(digest.as_mut_ptr().cast(), _mm_shuffle_epi8(dcba, mask));
+                _mm_storeu_si128(digest.as_mut_ptr().add(16).cast(), _mm_shuffle_epi8(hgfe, mask));
+            }
+        }
+    }
+}
+
+/// 为一种向量宽度生成多通道压缩函数
+#[cfg(target_arch = "x86_64")]
+macro_rules! multi_lane {
+    ($module:ident, $feature:literal, $lanes:literal, $vec:ty,
+     $set1:ident, $add:ident, $xor:ident, $and:ident, $or:ident, $andnot:ident,
+     $srli:ident, $slli:ident, $loadu:ident, $storeu:ident) => {
+        mod $module {
+            use std::arch::x86_64::*;
+
+            use super::{BLOCK_LEN, K};
+
+            const LANES: usize = $lanes;
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            fn rotr<const R: i32, const L: i32>(x: $vec) -> $vec {
+                $or($srli::<R>(x), $slli::<L>(x))
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            fn big_sigma0(x: $vec) -> $vec {
+                $xor($xor(rotr::<2, 30>(x), rotr::<13, 19>(x)), rotr::<22, 10>(x))
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            fn big_sigma1(x: $vec) -> $vec {
+                $xor($xor(rotr::<6, 26>(x), rotr::<11, 21>(x)), rotr::<25, 7>(x))
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            fn small_sigma0(x: $vec) -> $vec {
+                $xor($xor(rotr::<7, 25>(x), rotr::<18, 14>(x)), $srli::<3>(x))
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            fn small_sigma1(x: $vec) -> $vec {
+                $xor($xor(rotr::<17, 15>(x), rotr::<19, 13>(x)), $srli::<10>(x))
+            }
+
+            /// 每个通道第 `block` 个分组的第 `index` 个大端字
+            #[target_feature(enable = $feature)]
+            #[inline]
+            fn load_words(blocks: &[[u8; 2 * BLOCK_LEN]], block: usize, index: usize) -> $vec {
+                let offset = block * BLOCK_LEN + index * 4;
+                let mut words = [0u32; LANES];
+                for (word, lane) in words.iter_mut().zip(blocks) {
+                    *word = u32::from_be_bytes([lane[offset], lane[offset + 1], lane[offset + 2], lane[offset + 3]]);
+                }
+                // SAFETY: `words` 正好是一个向量的大小，loadu 不要求对齐
+                unsafe { $loadu(words.as_ptr().cast()) }
+            }
+
+            #[target_feature(enable = $feature)]
+            pub(super) fn compress(
+                state: &[u32; 8],
+                blocks: &[[u8; 2 * BLOCK_LEN]],
+                nblocks: usize,
+                digests: &mut [[u8; 32]],
+            ) {
+                let mut h = [$set1(0); 8];
+                for (vector, &word) in h.iter_mut().zip(state) {
+                    *vector = $set1(word as i32);
+                }
+
+                for block in 0..nblocks {
+                    let mut w = [$set1(0); 16];
+                    for (index, word) in w.iter_mut().enumerate() {
+                        *word = load_words(blocks, block, index);
+                    }
+
+                    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+                    for (t, &k) in K.iter().enumerate() {
+                        // 消息扩展只保留最近 16 个字
+                        let wt = if t < 16 {
+                            w[t]
+                        } else {
+                            let next = $add(
+                                $add(small_sigma1(w[(t - 2) & 15]), w[(t - 7) & 15]),
+                                $add(small_sigma0(w[(t - 15) & 15]), w[t & 15]),
+                            );
+                            w[t & 15] = next;
+                            next
+                        };
+
+                        let ch = $xor($and(e, f), $andnot(e, g));
+                        let maj = $xor($xor($and(a, b), $and(a, c)), $and(b, c));
+                        let t1 = $add($add($add(hh, big_sigma1(e)), $add(ch, $set1(k as i32))), wt);
+                        let t2 = $add(big_sigma0(a), maj);
+
+                        hh = g;
+                        g = f;
+                        f = e;
+                        e = $add(d, t1);
+                        d = c;
+                        c = b;
+                        b = a;
+                        a = $add(t1, t2);
+                    }
+
+                    for (vector, working) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+                        *vector = $add(*vector, working);
+                    }
+                }
+
+                // 转置回每个通道的大端摘要
+                let mut words = [[0u32; LANES]; 8];
+                for (lanes, vector) in words.iter_mut().zip(h) {
+                    // SAFETY: `lanes` 正好是一个向量的大小，storeu 不要求对齐
+                    unsafe { $storeu(lanes.as_mut_ptr().cast(), vector) };
+                }
+                for (lane, digest) in digests.iter_mut().take(LANES).enumerate() {
+                    for (bytes, lanes) in digest.chunks_exact_mut(4).zip(&words) {
+                        bytes.copy_from_slice(&lanes[lane].to_be_bytes());
+                    }
+                }
+            }
+        }
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+multi_lane!(avx2, "avx2", 8, __m256i,
+            _mm256_set1_epi32, _mm256_add_epi32, _mm256_xor_si256, _mm256_and_si256, _mm256_or_si256,
+            _mm256_andnot_si256, _mm256_srli_epi32, _mm256_slli_epi32, _mm256_loadu_si256, _mm256_storeu_si256);
+
+#[cfg(target_arch = "x86_64")]
+multi_lane!(sse41, "sse4.1", 4, __m128i,
+            _mm_set1_epi32, _mm_add_epi32, _mm_xor_si128, _mm_and_si128, _mm_or_si128,
+            _mm_andnot_si128, _mm_srli_epi32, _mm_slli_epi32, _mm_loadu_si128, _mm_storeu_si128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supported_backends_match_sha2() {
+        for backend in [Backend::Scalar, Backend::ShaNi, Backend::Sse41, Backend::Avx2] {
+            if backend.is_supported() {
+                assert!(backend.self_check(), "{} 的结果与 sha2 不一致", backend);
+            }
+        }
+    }
+
+    #[test]
+    fn detect_picks_supported_backend() {
+        assert!(Backend::detect().is_supported());
+    }
+}
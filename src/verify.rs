@@ -0,0 +1,104 @@
+use crate::difficulty::{leading_zero_bits, Difficulty};
+use crate::encoding::NonceEncoding;
+use crate::hash::Algorithm;
+use crate::search::NonceInput;
+
+/// 对一个nonce的验证结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    /// 参与哈希的完整输入（前缀 + 编码后的nonce）
+    pub input: Vec<u8>,
+    /// 原始摘要
+    pub digest: [u8; 32],
+    /// 十六进制哈希值
+    pub hash: String,
+    /// 哈希值实际达到的前导零比特数
+    pub leading_zero_bits: u32,
+    /// 要求的难度
+    pub difficulty: Difficulty,
+    pub algorithm: Algorithm,
+    pub nonce_encoding: NonceEncoding,
+}
+
+impl Verification {
+    /// 哈希值实际达到的十六进制前导零数量
+    pub fn leading_hex_zeros(&self) -> u32 {
+        self.leading_zero_bits / 4
+    }
+
+    /// 摘要是否满足难度要求
+    pub fn is_valid(&self) -> bool {
+        self.difficulty.is_met_by(&self.digest)
+    }
+}
+
+/// 重新计算 prefix + nonce 的 SHA-256 哈希值并检查是否满足难度
+pub fn verify(prefix: &str, nonce: u64, difficulty: Difficulty) -> Verification {
+    verify_with(prefix, nonce, difficulty, Algorithm::Sha256, NonceEncoding::Decimal)
+}
+
+/// 用指定的哈希算法和nonce编码验证
+pub fn verify_with(
+    prefix: &str,
+    nonce: u64,
+    difficulty: Difficulty,
+    algorithm: Algorithm,
+    nonce_encoding: NonceEncoding,
+) -> Verification {
+    // 按挖矿时相同的方式拼接 prefix 与 nonce
+    let mut input = NonceInput::with_encoding(prefix.as_bytes(), nonce_encoding.clone());
+    input.set(nonce);
+    let digest = algorithm.digest(input.as_bytes());
+
+    Verification {
+        input: input.as_bytes().to_vec(),
+        digest,
+        hash: hex::encode(digest),
+        leading_zero_bits: leading_zero_bits(&digest),
+        difficulty,
+        algorithm,
+        nonce_encoding,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::Sha256;
+    use crate::search::Searcher;
+
+    const HASH: &str = "00006b34895e909bee82d27ba3d180f270ec609e82eb561eec25a160003b971a";
+
+    #[test]
+    fn readme_vector() {
+        let verification = verify("weimeityy", 88_493, Difficulty::from_hex_zeros(4));
+        assert!(verification.is_valid());
+        assert_eq!(verification.input, b"weimeityy88493");
+        assert_eq!(verification.hash, HASH);
+        assert_eq!(verification.leading_zero_bits, 17);
+        assert_eq!(verification.leading_hex_zeros(), 4);
+
+        assert!(!verify("weimeityy", 88_493, Difficulty::from_hex_zeros(5)).is_valid());
+        assert!(verify("weimeityy", 88_493, Difficulty::from_bits(17)).is_valid());
+        assert!(!verify("weimeityy", 88_493, Difficulty::from_bits(18)).is_valid());
+        assert!(!verify("weimeityy", 88_492, Difficulty::from_hex_zeros(4)).is_valid());
+    }
+
+    #[test]
+    fn input_matches_search() {
+        // 搜索时找到的摘要与验证时重新计算的一致
+        let mut searcher = Searcher::<Sha256>::new(b"weimeityy", Difficulty::from_hex_zeros(4));
+        let (nonce, digest) = searcher.search(88_000, 90_000, |_| true).unwrap();
+        assert_eq!(nonce, 88_493);
+        assert_eq!(hex::encode(digest), HASH);
+
+        let encoding = NonceEncoding::DecimalPadded(8);
+        let mut searcher =
+            Searcher::<Sha256>::with_encoding(b"weimeityy", Difficulty::from_bits(8), encoding.clone());
+        let (nonce, digest) = searcher.search(0, 10_000, |_| true).unwrap();
+        let verification = verify_with("weimeityy", nonce, Difficulty::from_bits(8), Algorithm::Sha256, encoding);
+        assert!(verification.is_valid());
+        assert_eq!(verification.digest, digest);
+        assert_eq!(verification.input, format!("weimeityy{:08}", nonce).as_bytes());
+    }
+}